@@ -0,0 +1,134 @@
+use crate::model::{Chart, ChartSeries, Drawing};
+use crate::ooxml::{Element, Package};
+
+/// グラフ部品（`xl/charts/chart*.xml`）を読む。配置は描画側で読んだものを引き継ぐ
+pub fn read_chart(package: &mut Package, path: &str, drawing: &Drawing) -> Option<Chart> {
+    let space = package.part(path)?;
+    let chart = space.child("chart")?;
+    let plots: Vec<&Element> = chart
+        .child("plotArea")?
+        .children
+        .iter()
+        .filter(|c| c.name.ends_with("Chart"))
+        .collect();
+    let first = plots.first()?;
+    let chart_type = plot_type(first);
+
+    let mut series = Vec::new();
+    for plot in &plots {
+        let plot_type = plot_type(plot);
+        for ser in plot.children_named("ser") {
+            series.push(ChartSeries {
+                chart_type: (plot_type != chart_type).then(|| plot_type.clone()),
+                ..read_series(ser)
+            });
+        }
+    }
+
+    Some(Chart {
+        sheet: drawing.sheet.clone(),
+        name: drawing.name.clone(),
+        range: drawing.range.clone(),
+        x_offset: drawing.x_offset,
+        y_offset: drawing.y_offset,
+        width: drawing.width,
+        height: drawing.height,
+        bar_direction: value(first, "barDir"),
+        grouping: value(first, "grouping"),
+        style: scatter_style(first).or_else(|| value(first, "radarStyle")),
+        chart_type,
+        title: chart.child("title").and_then(title_text),
+        series,
+    })
+}
+
+/// `barChart` → `bar` のように要素名から `Chart` を除く
+fn plot_type(plot: &Element) -> String {
+    plot.name.trim_end_matches("Chart").to_string()
+}
+
+/// Excel はマーカーのみの散布図も `scatterStyle="lineMarker"` で保存し、系列の線を
+/// `spPr/a:ln/a:noFill` で消している。すべての系列の線が消えていれば `marker` とする
+fn scatter_style(plot: &Element) -> Option<String> {
+    let style = value(plot, "scatterStyle")?;
+    let mut series = plot.children_named("ser").peekable();
+    let hidden = series.peek().is_some()
+        && series.all(|ser| {
+            ser.child("spPr")
+                .and_then(|sp| sp.child("ln"))
+                .and_then(|ln| ln.child("noFill"))
+                .is_some()
+        });
+    Some(if hidden { "marker".to_string() } else { style })
+}
+
+/// `<c:barDir val="col"/>` のような子要素の `val`
+fn value(parent: &Element, name: &str) -> Option<String> {
+    parent.child(name)?.attr("val").map(ToString::to_string)
+}
+
+fn read_series(ser: &Element) -> ChartSeries {
+    let tx = ser.child("tx");
+    let name_ref = tx.and_then(|t| t.child("strRef"));
+    ChartSeries {
+        name: name_ref
+            .and_then(cached_text)
+            .or_else(|| tx.and_then(|t| t.child("v")).map(|v| v.text.clone())),
+        name_ref: name_ref.and_then(formula),
+        // 散布図・バブルチャートは X / Y 値として持つ
+        categories: ser
+            .child("cat")
+            .or_else(|| ser.child("xVal"))
+            .and_then(reference),
+        values: ser
+            .child("val")
+            .or_else(|| ser.child("yVal"))
+            .and_then(reference),
+        chart_type: None,
+    }
+}
+
+/// `numRef` / `strRef` / `multiLvlStrRef` のいずれかが持つ参照式
+fn reference(data: &Element) -> Option<String> {
+    ["numRef", "strRef", "multiLvlStrRef"]
+        .iter()
+        .find_map(|name| data.child(name))
+        .and_then(formula)
+}
+
+fn formula(reference: &Element) -> Option<String> {
+    let f = reference.child("f")?.text.trim();
+    (!f.is_empty()).then(|| f.trim_start_matches('=').to_string())
+}
+
+/// `strRef` にキャッシュされた値。複数セルなら空白でつなぐ
+fn cached_text(reference: &Element) -> Option<String> {
+    let text = reference
+        .child("strCache")?
+        .children_named("pt")
+        .filter_map(|pt| pt.child("v"))
+        .map(|v| v.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// 直接入力されたタイトル（`rich`）か、セル参照のキャッシュ値
+fn title_text(title: &Element) -> Option<String> {
+    let tx = title.child("tx")?;
+    if let Some(reference) = tx.child("strRef") {
+        return cached_text(reference);
+    }
+    let text = tx
+        .child("rich")?
+        .children_named("p")
+        .map(|p| {
+            p.children_named("r")
+                .filter_map(|r| r.child("t"))
+                .map(|t| t.text.as_str())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!text.is_empty()).then_some(text)
+}
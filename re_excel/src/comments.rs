@@ -0,0 +1,115 @@
+use crate::model::{Comment, CommentReply, parse_address};
+use crate::ooxml::{Element, Package};
+use std::collections::HashMap;
+
+/// ワークシート部品に関連付けられたコメントをセル位置順に読む。
+/// スレッド形式のコメントがあるセルでは、互換用に併記される旧形式のコメントは使わない
+pub fn read_comments(package: &mut Package, sheet: &str, sheet_path: &str) -> Vec<Comment> {
+    let rels = package.relationships(sheet_path);
+    let mut comments = Vec::new();
+
+    if let Some(threaded) = rels
+        .iter()
+        .find(|r| r.is("threadedComment"))
+        .and_then(|r| package.part(&r.target))
+    {
+        let persons = read_persons(package);
+        comments.extend(threaded_comments(&threaded, sheet, &persons));
+    }
+
+    if let Some(legacy) = rels
+        .iter()
+        .find(|r| r.is("comments"))
+        .and_then(|r| package.part(&r.target))
+    {
+        let authors: Vec<String> = legacy
+            .child("authors")
+            .into_iter()
+            .flat_map(|a| a.children_named("author"))
+            .map(|a| a.text.clone())
+            .collect();
+        let threaded = comments.len();
+        for comment in legacy
+            .child("commentList")
+            .into_iter()
+            .flat_map(|l| l.children_named("comment"))
+        {
+            let Some(address) = comment.attr("ref") else {
+                continue;
+            };
+            if comments[..threaded].iter().any(|c| c.address == address) {
+                continue;
+            }
+            comments.push(Comment {
+                sheet: sheet.to_string(),
+                address: address.to_string(),
+                author: comment
+                    .attr("authorId")
+                    .and_then(|i| i.parse::<usize>().ok())
+                    .and_then(|i| authors.get(i).cloned()),
+                text: comment
+                    .child("text")
+                    .map(Element::plain_text)
+                    .unwrap_or_default(),
+                replies: Vec::new(),
+            });
+        }
+    }
+
+    comments.sort_by_key(|c| parse_address(&c.address));
+    comments
+}
+
+/// `xl/persons/person.xml` の id → 表示名
+fn read_persons(package: &mut Package) -> HashMap<String, String> {
+    let Some(persons) = package
+        .relationships("xl/workbook.xml")
+        .into_iter()
+        .find(|r| r.is("person"))
+        .and_then(|r| package.part(&r.target))
+    else {
+        return HashMap::new();
+    };
+    persons
+        .children_named("person")
+        .filter_map(|p| {
+            Some((
+                p.attr("id")?.to_string(),
+                p.attr("displayName")?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// `parentId` を持たない要素をスレッドの先頭とし、返信を出現順にぶら下げる
+fn threaded_comments(
+    part: &Element,
+    sheet: &str,
+    persons: &HashMap<String, String>,
+) -> Vec<Comment> {
+    let author = |c: &Element| c.attr("personId").and_then(|id| persons.get(id)).cloned();
+    let text = |c: &Element| c.child("text").map(|t| t.text.clone()).unwrap_or_default();
+
+    let items: Vec<&Element> = part.children_named("threadedComment").collect();
+    items
+        .iter()
+        .filter(|c| c.attr("parentId").is_none())
+        .filter_map(|root| {
+            let id = root.attr("id");
+            Some(Comment {
+                sheet: sheet.to_string(),
+                address: root.attr("ref")?.to_string(),
+                author: author(root),
+                text: text(root),
+                replies: items
+                    .iter()
+                    .filter(|c| id.is_some() && c.attr("parentId") == id)
+                    .map(|c| CommentReply {
+                        author: author(c),
+                        text: text(c),
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
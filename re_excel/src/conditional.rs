@@ -0,0 +1,155 @@
+use crate::model::{ConditionalFormat, ConditionalThreshold};
+use crate::ooxml::Element;
+use crate::styles::Colors;
+
+/// ワークシートの条件付き書式を優先順位順に読む。拡張領域（x14 形式）のみに書かれた
+/// ルールも含めるが、x14 のデータバーは標準領域のルールの補足なので読まない
+pub fn read_conditional_formats(
+    sheet: &str,
+    sheet_xml: &Element,
+    colors: &Colors,
+) -> Vec<ConditionalFormat> {
+    let mut rules = Vec::new();
+
+    for block in sheet_xml.children_named("conditionalFormatting") {
+        let Some(range) = block.attr("sqref") else {
+            continue;
+        };
+        for rule in block.children_named("cfRule") {
+            rules.extend(read_rule(sheet, range, rule, colors));
+        }
+    }
+
+    let extended = sheet_xml
+        .child("extLst")
+        .into_iter()
+        .flat_map(|e| e.children_named("ext"))
+        .filter_map(|e| e.child("conditionalFormattings"))
+        .flat_map(|c| c.children_named("conditionalFormatting"));
+    for block in extended {
+        let Some(range) = block.child("sqref").map(|s| s.text.as_str()) else {
+            continue;
+        };
+        for rule in block
+            .children_named("cfRule")
+            .filter(|r| r.attr("type") != Some("dataBar"))
+        {
+            rules.extend(read_rule(sheet, range, rule, colors));
+        }
+    }
+
+    rules.sort_by_key(|r| r.priority);
+    rules
+}
+
+fn read_rule(
+    sheet: &str,
+    range: &str,
+    rule: &Element,
+    colors: &Colors,
+) -> Option<ConditionalFormat> {
+    let attr = |name: &str| rule.attr(name).map(ToString::to_string);
+    let flag = |name: &str| matches!(rule.attr(name), Some("1" | "true"));
+    let number = |name: &str| rule.attr(name).and_then(|v| v.parse::<u32>().ok());
+
+    // カラースケール・データバー・アイコンセットは子要素に閾値と色を持つ
+    let scale = ["colorScale", "dataBar", "iconSet"]
+        .iter()
+        .find_map(|name| rule.child(name));
+    let thresholds = scale
+        .into_iter()
+        .flat_map(|s| s.children_named("cfvo"))
+        .map(read_threshold)
+        .collect();
+    let scale_colors = scale
+        .into_iter()
+        .flat_map(|s| s.children_named("color"))
+        .filter_map(|c| colors.resolve(c))
+        .collect();
+
+    Some(ConditionalFormat {
+        sheet: sheet.to_string(),
+        range: range.to_string(),
+        rule_type: rule.attr("type")?.to_string(),
+        priority: number("priority").unwrap_or_default(),
+        stop_if_true: flag("stopIfTrue"),
+        style_id: rule.attr("dxfId").and_then(|v| v.parse().ok()),
+        operator: attr("operator"),
+        formulas: rule
+            .children_named("formula")
+            .chain(rule.children_named("f"))
+            .map(|f| f.text.clone())
+            .collect(),
+        text: attr("text"),
+        time_period: attr("timePeriod"),
+        rank: number("rank"),
+        percent: flag("percent"),
+        bottom: flag("bottom"),
+        below_average: matches!(rule.attr("aboveAverage"), Some("0" | "false")),
+        equal_average: flag("equalAverage"),
+        std_dev: number("stdDev"),
+        icon_set: rule
+            .child("iconSet")
+            .map(|i| i.attr("iconSet").unwrap_or("3TrafficLights1").to_string()),
+        reverse: scale.is_some_and(|s| matches!(s.attr("reverse"), Some("1" | "true"))),
+        hide_value: scale.is_some_and(|s| matches!(s.attr("showValue"), Some("0" | "false"))),
+        thresholds,
+        colors: scale_colors,
+    })
+}
+
+/// `<cfvo type="percent" val="50"/>`。x14 形式では値が `<xm:f>` 子要素になる
+fn read_threshold(cfvo: &Element) -> ConditionalThreshold {
+    ConditionalThreshold {
+        value_type: cfvo.attr("type").unwrap_or("num").to_string(),
+        value: cfvo
+            .attr("val")
+            .map(ToString::to_string)
+            .or_else(|| cfvo.child("f").map(|f| f.text.clone())),
+        greater_than: matches!(cfvo.attr("gte"), Some("0" | "false")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_rules_in_priority_order() {
+        let xml = r#"<worksheet><conditionalFormatting sqref="A1:A10"><cfRule type="cellIs" dxfId="0" priority="2" operator="lessThan"><formula>10</formula></cfRule><cfRule type="cellIs" dxfId="1" priority="1" stopIfTrue="1" operator="greaterThan"><formula>50</formula></cfRule></conditionalFormatting><conditionalFormatting sqref="B1:B10"><cfRule type="iconSet" priority="3"><iconSet iconSet="3Arrows" showValue="0" reverse="1"><cfvo type="percent" val="0"/><cfvo type="percent" val="33"/><cfvo type="percent" val="67" gte="0"/></iconSet></cfRule></conditionalFormatting><extLst><ext uri="{78C0D931-6437-407d-A8EE-F0AAD7539E65}" xmlns:x14="http://schemas.microsoft.com/office/spreadsheetml/2009/9/main"><x14:conditionalFormattings><x14:conditionalFormatting xmlns:xm="http://schemas.microsoft.com/office/excel/2006/main"><x14:cfRule type="dataBar" id="{00000000-0000-0000-0000-000000000001}"><x14:dataBar minLength="0" maxLength="100"/></x14:cfRule><xm:sqref>C1:C10</xm:sqref></x14:conditionalFormatting><x14:conditionalFormatting xmlns:xm="http://schemas.microsoft.com/office/excel/2006/main"><x14:cfRule type="iconSet" priority="4" id="{00000000-0000-0000-0000-000000000002}"><x14:iconSet iconSet="3Stars"><x14:cfvo type="percent"><xm:f>0</xm:f></x14:cfvo><x14:cfvo type="percent"><xm:f>33</xm:f></x14:cfvo><x14:cfvo type="percent"><xm:f>67</xm:f></x14:cfvo></x14:iconSet></x14:cfRule><xm:sqref>D1:D10</xm:sqref></x14:conditionalFormatting></x14:conditionalFormattings></ext></extLst></worksheet>"#;
+        let rules = read_conditional_formats(
+            "Sheet1",
+            &crate::ooxml::parse_xml(xml.as_bytes()).unwrap(),
+            &Colors::default(),
+        );
+        // x14 のデータバーは標準領域の規則の補足なので数えない
+        assert_eq!(rules.len(), 4);
+
+        assert_eq!(rules[0].range, "A1:A10");
+        assert_eq!(rules[0].operator.as_deref(), Some("greaterThan"));
+        assert_eq!(rules[0].formulas, ["50"]);
+        assert_eq!(rules[0].style_id, Some(1));
+        assert!(rules[0].stop_if_true);
+        assert_eq!(rules[1].operator.as_deref(), Some("lessThan"));
+        assert_eq!(rules[1].style_id, Some(0));
+
+        let icons = &rules[2];
+        assert_eq!(icons.rule_type, "iconSet");
+        assert_eq!(icons.icon_set.as_deref(), Some("3Arrows"));
+        assert!(icons.reverse && icons.hide_value);
+        let values: Vec<_> = icons
+            .thresholds
+            .iter()
+            .map(|t| t.value.as_deref())
+            .collect();
+        assert_eq!(values, [Some("0"), Some("33"), Some("67")]);
+        assert!(icons.thresholds[2].greater_than);
+
+        // x14 形式では閾値が `<xm:f>` 子要素になる
+        let stars = &rules[3];
+        assert_eq!(stars.range, "D1:D10");
+        assert_eq!(stars.icon_set.as_deref(), Some("3Stars"));
+        assert_eq!(stars.thresholds[1].value.as_deref(), Some("33"));
+        assert!(!stars.thresholds[1].greater_than);
+    }
+}
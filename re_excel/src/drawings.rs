@@ -0,0 +1,351 @@
+use crate::charts;
+use crate::model::{Chart, Drawing, DrawingKind, col_to_letter};
+use crate::ooxml::{Element, Package, Relationship};
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const EMU_PER_PIXEL: f64 = 9525.0;
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// 画像の中身の出力方法。既定では出力しない
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum MediaMode {
+    #[default]
+    None,
+    /// `data` に base64 で埋め込む
+    Inline,
+    /// 出力した文書と画像ファイルを 1 つの ZIP にまとめる
+    Zip,
+}
+
+impl MediaMode {
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(MediaMode::None),
+            "inline" => Some(MediaMode::Inline),
+            "zip" => Some(MediaMode::Zip),
+            _ => None,
+        }
+    }
+}
+
+/// アンカー要素から読み取った配置
+#[derive(Default)]
+struct Placement {
+    range: Option<String>,
+    x_offset: u32,
+    y_offset: u32,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+/// ワークシート（チャートシート）に関連付けられた描画部品を読む。
+/// `include_media` なら画像の中身を base64 で `data` に持たせる。
+/// グラフは描画としての配置に加え、定義を読んだものを合わせて返す
+pub fn read_drawings(
+    package: &mut Package,
+    sheet: &str,
+    sheet_path: &str,
+    include_media: bool,
+) -> (Vec<Drawing>, Vec<Chart>) {
+    let targets: Vec<String> = package
+        .relationships(sheet_path)
+        .into_iter()
+        .filter(|r| r.is("drawing"))
+        .map(|r| r.target)
+        .collect();
+
+    let mut drawings = Vec::new();
+    let mut charts = Vec::new();
+    for target in &targets {
+        let Some(part) = package.part(target) else {
+            continue;
+        };
+        let rels = package.relationships(target);
+        for anchor in &part.children {
+            if !matches!(
+                anchor.name.as_str(),
+                "twoCellAnchor" | "oneCellAnchor" | "absoluteAnchor"
+            ) {
+                continue;
+            }
+            let placement = placement(anchor);
+            let mut objects = Vec::new();
+            collect_objects(anchor, &mut objects);
+            for object in objects {
+                let mut drawing = read_object(object, sheet, &placement, &rels);
+                if let Some(path) = &drawing.media {
+                    drawing.media_type = package.content_type(path);
+                    if include_media {
+                        drawing.data = package.raw_part(path).map(|b| base64_encode(&b));
+                    }
+                }
+                let chart_path = chart_reference(object)
+                    .and_then(|c| c.attr("id"))
+                    .and_then(|id| rels.iter().find(|r| r.id == id))
+                    .map(|r| r.target.clone());
+                if let Some(chart) =
+                    chart_path.and_then(|path| charts::read_chart(package, &path, &drawing))
+                {
+                    charts.push(chart);
+                }
+                drawings.push(drawing);
+            }
+        }
+    }
+    (drawings, charts)
+}
+
+/// グラフィックフレームが参照するグラフ（`graphic/graphicData/chart`）
+fn chart_reference(object: &Element) -> Option<&Element> {
+    object
+        .child("graphic")?
+        .child("graphicData")?
+        .child("chart")
+}
+
+/// アンカー直下の描画オブジェクトを列挙する。グループは中身を展開する
+fn collect_objects<'e>(parent: &'e Element, objects: &mut Vec<&'e Element>) {
+    for child in &parent.children {
+        match child.name.as_str() {
+            "pic" | "sp" | "cxnSp" | "graphicFrame" => objects.push(child),
+            "grpSp" => collect_objects(child, objects),
+            // 新しい機能を使った図形は互換用の表現と併記される。新しい方を使う
+            "AlternateContent" => {
+                if let Some(choice) = child.child("Choice") {
+                    collect_objects(choice, objects);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn placement(anchor: &Element) -> Placement {
+    let number = |parent: &Element, name: &str| -> Option<i64> {
+        parent.child(name)?.text.trim().parse().ok()
+    };
+    // 負の番号や u32 に収まらない番号のマーカーは無視する
+    let cell = |marker: &Element| -> Option<String> {
+        let index = |name: &str| u32::try_from(number(marker, name)?).ok()?.checked_add(1);
+        Some(format!("{}{}", col_to_letter(index("col")?), index("row")?))
+    };
+    let mut placement = Placement::default();
+
+    if let Some(from) = anchor.child("from") {
+        placement.range = cell(from);
+        placement.x_offset = number(from, "colOff").map_or(0, pixels);
+        placement.y_offset = number(from, "rowOff").map_or(0, pixels);
+    }
+    if let (Some(range), Some(to)) = (&mut placement.range, anchor.child("to").and_then(cell)) {
+        range.push(':');
+        range.push_str(&to);
+    }
+    if let Some(ext) = anchor.child("ext") {
+        placement.width = ext.attr("cx").and_then(|v| v.parse().ok()).map(pixels);
+        placement.height = ext.attr("cy").and_then(|v| v.parse().ok()).map(pixels);
+    }
+    placement
+}
+
+fn read_object(
+    object: &Element,
+    sheet: &str,
+    placement: &Placement,
+    rels: &[Relationship],
+) -> Drawing {
+    let target = |id: Option<&str>| -> Option<String> {
+        let id = id?;
+        rels.iter().find(|r| r.id == id).map(|r| r.target.clone())
+    };
+    let kind = match object.name.as_str() {
+        "pic" => DrawingKind::Image,
+        "sp" => DrawingKind::Shape,
+        "cxnSp" => DrawingKind::Connector,
+        _ if chart_reference(object).is_some() => DrawingKind::Chart,
+        _ => DrawingKind::Graphic,
+    };
+
+    let properties = object
+        .children
+        .iter()
+        .find(|c| c.name.starts_with("nv"))
+        .and_then(|nv| nv.child("cNvPr"));
+    let property = |name: &str| {
+        properties
+            .and_then(|p| p.attr(name))
+            .filter(|v| !v.is_empty())
+            .map(ToString::to_string)
+    };
+    let shape_properties = object.child("spPr");
+    // グラフィックフレームは `xfrm` を直下に持つ
+    let ext = shape_properties
+        .and_then(|p| p.child("xfrm"))
+        .or_else(|| object.child("xfrm"))
+        .and_then(|x| x.child("ext"));
+    let size = |name: &str| -> Option<u32> { ext?.attr(name)?.parse().ok().map(pixels) };
+
+    Drawing {
+        sheet: sheet.to_string(),
+        kind,
+        name: property("name"),
+        range: placement.range.clone(),
+        x_offset: placement.x_offset,
+        y_offset: placement.y_offset,
+        width: size("cx").or(placement.width),
+        height: size("cy").or(placement.height),
+        alt_text: property("descr").or_else(|| property("title")),
+        shape_type: shape_properties
+            .and_then(|p| p.child("prstGeom"))
+            .and_then(|g| g.attr("prst"))
+            .map(ToString::to_string),
+        text: object.child("txBody").and_then(shape_text),
+        media: match kind {
+            DrawingKind::Image => target(
+                object
+                    .child("blipFill")
+                    .and_then(|f| f.child("blip"))
+                    .and_then(|b| b.attr("embed")),
+            ),
+            _ => None,
+        },
+        media_type: None,
+        data: None,
+    }
+}
+
+/// 段落ごとに `<a:t>` をつなげ、改行で区切る
+fn shape_text(body: &Element) -> Option<String> {
+    let text = body
+        .children_named("p")
+        .map(|p| {
+            p.children
+                .iter()
+                .filter_map(|r| r.child("t"))
+                .map(|t| t.text.as_str())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!text.trim().is_empty()).then_some(text)
+}
+
+fn pixels(emu: i64) -> u32 {
+    (emu.max(0) as f64 / EMU_PER_PIXEL).round() as u32
+}
+
+/// 変換結果の文書と、描画が参照する画像を 1 つの ZIP にまとめる。
+/// 画像は `Drawing::media` と同じパスで格納する
+pub fn media_archive(
+    document_name: &str,
+    document: &[u8],
+    source: &[u8],
+    drawings: &[Drawing],
+) -> zip::result::ZipResult<Vec<u8>> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer.start_file(document_name, options)?;
+    writer.write_all(document)?;
+
+    let mut package = Package::open(source);
+    let mut written: Vec<&str> = Vec::new();
+    for path in drawings.iter().filter_map(|d| d.media.as_deref()) {
+        if written.contains(&path) {
+            continue;
+        }
+        if let Some(bytes) = package.as_mut().and_then(|p| p.raw_part(path)) {
+            writer.start_file(path, options)?;
+            writer.write_all(&bytes)?;
+            written.push(path);
+        }
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// 空白を無視して復号する。長さが 4 の倍数でない、末尾以外に `=` がある、
+/// 不正な文字を含むといった場合は `None`
+pub fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let padding = text.iter().rev().take_while(|&&c| c == b'=').count();
+    if padding > 2 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for &c in &text[..text.len() - padding] {
+        let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+        buffer = buffer << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_round_trips() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 97 + 200) as u8).collect();
+            assert_eq!(base64_decode(&base64_encode(&bytes)), Some(bytes));
+        }
+        assert_eq!(base64_decode("Zm9v\nYmFy"), Some(b"foobar".to_vec()));
+        assert_eq!(base64_decode("Zm9v!"), None);
+        assert_eq!(base64_decode("Zm9vY"), None);
+        assert_eq!(base64_decode("Zg=="), Some(b"f".to_vec()));
+        assert_eq!(base64_decode("Zg=a"), None);
+        assert_eq!(base64_decode("Z==="), None);
+        assert_eq!(base64_decode("Zg==Zm9v"), None);
+    }
+
+    #[test]
+    fn ignores_out_of_range_markers() {
+        let anchor = |col: &str, row: &str| {
+            crate::ooxml::parse_xml(
+                format!(
+                    "<twoCellAnchor><from><col>{col}</col><row>{row}</row></from>\
+                     <to><col>3</col><row>4</row></to></twoCellAnchor>"
+                )
+                .as_bytes(),
+            )
+            .unwrap()
+        };
+        assert_eq!(placement(&anchor("1", "2")).range.as_deref(), Some("B3:D5"));
+        assert_eq!(placement(&anchor("-1", "0")).range, None);
+        assert_eq!(placement(&anchor("0", "-1")).range, None);
+        assert_eq!(placement(&anchor("4294967295", "0")).range, None);
+    }
+}
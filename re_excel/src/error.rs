@@ -0,0 +1,189 @@
+use axum::{
+    Json,
+    extract::multipart::MultipartError,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::fmt;
+
+/// API 全体で共通のエラー型。JSON `{ error, message, details }` として返す
+#[derive(Debug)]
+pub enum AppError {
+    /// multipart の読み取りに失敗した（切り詰められた本文など）
+    Multipart { status: StatusCode, message: String },
+    /// 必須フィールドが無い
+    MissingField(&'static str),
+    /// フィールドの値を読み取れない（UTF-8 でないなど）
+    InvalidField {
+        field: &'static str,
+        message: String,
+    },
+    /// パラメータ値がサポート外
+    UnsupportedFormat { field: &'static str, value: String },
+    /// 入力ファイルの種類を判別できない
+    UnrecognizedInput { filename: Option<String> },
+    /// テキスト入力が UTF-8 でない（Shift_JIS の CSV など）
+    InvalidEncoding { cell: Option<String> },
+    /// コンテナ（ZIP / OLE2 など）を開けない
+    CorruptContainer { format: String, message: String },
+    /// シート（またはセル）の読み取りに失敗した
+    SheetRead {
+        sheet: String,
+        cell: Option<String>,
+        message: String,
+    },
+    /// 入力ドキュメントが不正（空のファイル・逆変換の入力の構文エラーなど）
+    InvalidDocument {
+        format: String,
+        message: String,
+        location: Option<ErrorLocation>,
+    },
+    /// 入力ドキュメントの内容から .xlsx を組み立てられない（シート名の重複など）
+    Restore {
+        sheet: Option<String>,
+        message: String,
+    },
+    /// 出力の直列化（.xlsx 書き出しを含む）に失敗した
+    Serialization(String),
+    /// 想定外の内部エラー（処理スレッドの panic など）
+    Internal(String),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorLocation {
+    pub sheet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: &'static str,
+    message: String,
+    details: Vec<ErrorLocation>,
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Multipart { .. } => "MultipartError",
+            AppError::MissingField(_) => "MissingField",
+            AppError::InvalidField { .. } => "InvalidField",
+            AppError::UnsupportedFormat { .. } => "UnsupportedFormat",
+            AppError::UnrecognizedInput { .. } => "UnrecognizedInput",
+            AppError::InvalidEncoding { .. } => "InvalidEncoding",
+            AppError::CorruptContainer { .. } => "CorruptContainer",
+            AppError::SheetRead { .. } => "SheetReadError",
+            AppError::InvalidDocument { .. } => "ParseError",
+            AppError::Restore { .. } => "RestoreError",
+            AppError::Serialization(_) => "SerializationError",
+            AppError::Internal(_) => "InternalError",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Multipart { status, .. } => *status,
+            AppError::MissingField(_)
+            | AppError::InvalidField { .. }
+            | AppError::UnsupportedFormat { .. }
+            | AppError::InvalidDocument { .. } => StatusCode::BAD_REQUEST,
+            AppError::UnrecognizedInput { .. } | AppError::InvalidEncoding { .. } => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+            AppError::CorruptContainer { .. }
+            | AppError::SheetRead { .. }
+            | AppError::Restore { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Serialization(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn details(self) -> Vec<ErrorLocation> {
+        match self {
+            AppError::SheetRead { sheet, cell, .. } => vec![ErrorLocation { sheet, cell }],
+            AppError::InvalidEncoding { cell: Some(cell) } => vec![ErrorLocation {
+                sheet: "Sheet1".into(),
+                cell: Some(cell),
+            }],
+            AppError::InvalidDocument {
+                location: Some(location),
+                ..
+            } => vec![location],
+            AppError::Restore {
+                sheet: Some(sheet), ..
+            } => vec![ErrorLocation { sheet, cell: None }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Multipart { message, .. } => {
+                write!(f, "Invalid multipart request: {}", message)
+            }
+            AppError::MissingField(name) => write!(f, "Missing '{}'", name),
+            AppError::InvalidField { field, message } => {
+                write!(f, "Invalid '{}': {}", field, message)
+            }
+            AppError::UnsupportedFormat { field, value } => {
+                write!(f, "Unsupported '{}': {}", field, value)
+            }
+            AppError::UnrecognizedInput { filename } => write!(
+                f,
+                "Unrecognized spreadsheet file: {}",
+                filename.as_deref().unwrap_or("(unnamed)")
+            ),
+            AppError::InvalidEncoding { cell: Some(cell) } => {
+                write!(f, "CSV must be UTF-8: invalid byte sequence at {}", cell)
+            }
+            AppError::InvalidEncoding { .. } => write!(f, "CSV must be UTF-8"),
+            AppError::CorruptContainer { format, message } => {
+                write!(f, "Cannot open {} file: {}", format, message)
+            }
+            AppError::SheetRead {
+                sheet,
+                cell: Some(cell),
+                message,
+            } => write!(f, "Error reading sheet {} at {}: {}", sheet, cell, message),
+            AppError::SheetRead { sheet, message, .. } => {
+                write!(f, "Error reading sheet {}: {}", sheet, message)
+            }
+            AppError::InvalidDocument {
+                format, message, ..
+            } => write!(f, "Invalid {} document: {}", format, message),
+            AppError::Restore {
+                sheet: Some(sheet),
+                message,
+            } => write!(f, "Cannot restore sheet {}: {}", sheet, message),
+            AppError::Restore { message, .. } => write!(f, "Cannot restore workbook: {}", message),
+            AppError::Serialization(e) => write!(f, "Serialization error: {}", e),
+            AppError::Internal(e) => write!(f, "Internal error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<MultipartError> for AppError {
+    fn from(e: MultipartError) -> Self {
+        AppError::Multipart {
+            status: e.status(),
+            message: e.body_text(),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body = ErrorBody {
+            error: self.code(),
+            message: self.to_string(),
+            details: self.details(),
+        };
+        (status, Json(body)).into_response()
+    }
+}
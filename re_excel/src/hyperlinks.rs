@@ -0,0 +1,39 @@
+use crate::model::Hyperlink;
+use crate::ooxml::{Element, Package};
+
+/// ワークシートの `<hyperlinks>` を読む。外部リンクの URL はシートの `.rels` から解決する
+pub fn read_hyperlinks(
+    package: &mut Package,
+    sheet: &str,
+    sheet_path: &str,
+    sheet_xml: &Element,
+) -> Vec<Hyperlink> {
+    let Some(list) = sheet_xml.child("hyperlinks") else {
+        return Vec::new();
+    };
+    let rels = package.relationships(sheet_path);
+
+    list.children_named("hyperlink")
+        .filter_map(|link| {
+            let range = link.attr("ref")?;
+            let location = link.attr("location").filter(|l| !l.is_empty());
+            let url = link
+                .attr("id")
+                .and_then(|id| rels.iter().find(|r| r.id == id))
+                .map(|r| r.target.as_str());
+            let (target, internal) = match (url, location) {
+                (Some(url), Some(location)) => (format!("{}#{}", url, location), false),
+                (Some(url), None) => (url.to_string(), false),
+                (None, Some(location)) => (location.to_string(), true),
+                (None, None) => return None,
+            };
+            Some(Hyperlink {
+                sheet: sheet.to_string(),
+                range: range.to_string(),
+                target,
+                tooltip: link.attr("tooltip").map(ToString::to_string),
+                internal,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,184 @@
+use crate::model::{ColumnLayout, FreezePanes, RowLayout, SheetMetadata, col_to_letter};
+use crate::ooxml::Element;
+use crate::styles::Colors;
+
+/// 列幅・行の高さ・ウィンドウ枠の固定など、シートの表示設定を読み込む
+pub fn read_layout(sheet: &mut SheetMetadata, sheet_xml: &Element, colors: &Colors) {
+    sheet.tab_color = sheet_xml
+        .child("sheetPr")
+        .and_then(|p| p.child("tabColor"))
+        .and_then(|c| colors.resolve(c));
+
+    if let Some(view) = sheet_xml
+        .child("sheetViews")
+        .and_then(|v| v.child("sheetView"))
+    {
+        sheet.zoom = view.attr("zoomScale").and_then(|z| z.parse().ok());
+        sheet.hide_gridlines = matches!(view.attr("showGridLines"), Some("0" | "false"));
+        sheet.freeze_panes = view.child("pane").and_then(read_freeze_panes);
+    }
+
+    if let Some(format) = sheet_xml.child("sheetFormatPr") {
+        let number = |name: &str| format.attr(name).and_then(|v| v.parse().ok());
+        sheet.default_row_height = number("defaultRowHeight");
+        sheet.default_col_width = number("defaultColWidth");
+    }
+
+    sheet.columns = sheet_xml
+        .child("cols")
+        .into_iter()
+        .flat_map(|c| c.children_named("col"))
+        .filter_map(read_column)
+        .collect();
+    sheet.rows = sheet_xml
+        .child("sheetData")
+        .into_iter()
+        .flat_map(|d| d.children_named("row"))
+        .filter_map(|r| read_row(r, sheet.default_row_height))
+        .collect();
+}
+
+/// 分割（`split`）は固定ではないので読まない
+fn read_freeze_panes(pane: &Element) -> Option<FreezePanes> {
+    if !matches!(pane.attr("state"), Some("frozen" | "frozenSplit")) {
+        return None;
+    }
+    // 負の値や範囲外の値は分割なしとして扱う
+    let split = |name: &str| {
+        pane.attr(name)
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| (0.0..f64::from(u32::MAX)).contains(v))
+            .map_or(0, |v| v as u32)
+    };
+    let (rows, cols) = (split("ySplit"), split("xSplit"));
+    let default_cell = format!("{}{}", col_to_letter(cols + 1), rows + 1);
+    Some(FreezePanes {
+        rows,
+        cols,
+        top_left_cell: pane
+            .attr("topLeftCell")
+            .filter(|c| *c != default_cell)
+            .map(ToString::to_string),
+    })
+}
+
+/// 書式の指定のみの列は対象外
+fn read_column(col: &Element) -> Option<ColumnLayout> {
+    let flag = |name: &str| matches!(col.attr(name), Some("1" | "true"));
+    let layout = ColumnLayout {
+        first_col: col.attr("min")?.parse().ok()?,
+        last_col: col.attr("max")?.parse().ok()?,
+        width: col
+            .attr("width")
+            .filter(|_| flag("customWidth"))
+            .and_then(|w| w.parse().ok()),
+        hidden: flag("hidden"),
+        outline_level: col
+            .attr("outlineLevel")
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(0),
+        collapsed: flag("collapsed"),
+    };
+    (layout.width.is_some() || layout.hidden || layout.outline_level > 0 || layout.collapsed)
+        .then_some(layout)
+}
+
+/// 自動調整された高さと、既定の高さと同じ高さは保持しない
+fn read_row(row: &Element, default_height: Option<f64>) -> Option<RowLayout> {
+    let flag = |name: &str| matches!(row.attr(name), Some("1" | "true"));
+    let layout = RowLayout {
+        row: row.attr("r")?.parse().ok()?,
+        height: row
+            .attr("ht")
+            .filter(|_| flag("customHeight"))
+            .and_then(|h| h.parse().ok())
+            .filter(|h| Some(*h) != default_height),
+        hidden: flag("hidden"),
+        outline_level: row
+            .attr("outlineLevel")
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(0),
+        collapsed: flag("collapsed"),
+    };
+    (layout.height.is_some() || layout.hidden || layout.outline_level > 0 || layout.collapsed)
+        .then_some(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_outlines_and_sheet_view() {
+        let xml = r#"<worksheet><sheetPr><tabColor rgb="FFFF0000"/></sheetPr><sheetViews><sheetView zoomScale="80" showGridLines="0"><pane xSplit="1" ySplit="2" topLeftCell="B3" state="frozen"/></sheetView></sheetViews><sheetFormatPr defaultRowHeight="15" outlineLevelRow="2"/><cols><col min="2" max="3" width="9.140625" hidden="1" outlineLevel="1"/><col min="4" max="4" width="20" customWidth="1" collapsed="1"/></cols><sheetData><row r="1" ht="30" customHeight="1"/><row r="2" outlineLevel="1"/><row r="3" outlineLevel="2"/><row r="7" hidden="1" outlineLevel="1"/><row r="10" collapsed="1"/><row r="11" ht="15" customHeight="1"/><row r="12" ht="40"/></sheetData></worksheet>"#;
+        let mut sheet = SheetMetadata::default();
+        read_layout(
+            &mut sheet,
+            &crate::ooxml::parse_xml(xml.as_bytes()).unwrap(),
+            &Colors::default(),
+        );
+
+        assert_eq!(sheet.tab_color.as_deref(), Some("#FF0000"));
+        assert_eq!(sheet.zoom, Some(80));
+        assert!(sheet.hide_gridlines);
+        let panes = sheet.freeze_panes.unwrap();
+        // 固定位置どおりの左上セルは省く
+        assert_eq!((panes.rows, panes.cols, panes.top_left_cell), (2, 1, None));
+
+        let row =
+            |row: u32, height: Option<f64>, hidden: bool, outline_level: u8, collapsed| RowLayout {
+                row,
+                height,
+                hidden,
+                outline_level,
+                collapsed,
+            };
+        // 既定と同じ高さ・customHeight の無い高さは保持しない
+        assert_eq!(
+            sheet.rows,
+            [
+                row(1, Some(30.0), false, 0, false),
+                row(2, None, false, 1, false),
+                row(3, None, false, 2, false),
+                row(7, None, true, 1, false),
+                // 折りたたみの印はグループ直後の行に付く
+                row(10, None, false, 0, true),
+            ]
+        );
+        assert_eq!(
+            sheet.columns,
+            [
+                ColumnLayout {
+                    first_col: 2,
+                    last_col: 3,
+                    width: None,
+                    hidden: true,
+                    outline_level: 1,
+                    collapsed: false,
+                },
+                ColumnLayout {
+                    first_col: 4,
+                    last_col: 4,
+                    width: Some(20.0),
+                    hidden: false,
+                    outline_level: 0,
+                    collapsed: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_out_of_range_splits() {
+        let pane = |x_split: &str| {
+            let xml = format!(r#"<pane xSplit="{x_split}" ySplit="2" state="frozen"/>"#);
+            read_freeze_panes(&crate::ooxml::parse_xml(xml.as_bytes()).unwrap()).unwrap()
+        };
+        assert_eq!((pane("1").rows, pane("1").cols), (2, 1));
+        for x_split in ["-1", "4294967295", "1e300", "NaN"] {
+            let pane = pane(x_split);
+            assert_eq!(pane.cols, 0, "{}", x_split);
+            assert_eq!(pane.top_left_cell, None, "{}", x_split);
+        }
+    }
+}
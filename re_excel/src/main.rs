@@ -1,233 +1,1899 @@
-use axum::{Router, extract::Multipart, http::StatusCode, response::IntoResponse, routing::post};
-use calamine::{Data, Reader, Xlsx};
+mod charts;
+mod comments;
+mod conditional;
+mod drawings;
+mod error;
+mod hyperlinks;
+mod layout;
+mod model;
+mod numfmt;
+mod ooxml;
+mod output;
+mod parser;
+mod pivots;
+mod print;
+mod protection;
+mod restore;
+mod richtext;
+mod rows;
+mod sql;
+mod styles;
+mod tables;
+mod typed;
+mod validations;
+
+use axum::{
+    Router,
+    extract::{Multipart, multipart::Field},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use drawings::{MediaMode, media_archive};
+use error::AppError;
+use model::SourceFormat;
+use output::WorkbookOutput;
+use parser::{ParseOptions, parse_workbook};
+use restore::{parse_document, restore_xlsx};
+use rows::TableMode;
 use serde::Serialize;
-use std::io::Cursor;
+use sql::to_sql;
 use tokio::net::TcpListener;
+use typed::ValueMode;
 
-#[derive(Serialize)]
-struct Workbook {
-    sheets: Vec<SheetMetadata>,
-    cells: Vec<CellData>,
-    merged_ranges: Vec<MergedRange>,
-}
+#[tokio::main]
+async fn main() {
+    let listener = TcpListener::bind("0.0.0.0:8080")
+        .await
+        .expect("Failed to bind address");
+    let addr = listener.local_addr().unwrap();
+    println!("Listening on http://{}", addr);
 
-#[derive(Serialize)]
-struct SheetMetadata {
-    name: String,
-    index: usize,
-    hidden: bool,
+    axum::serve(listener, app()).await.unwrap();
 }
 
-#[derive(Serialize)]
-struct CellData {
-    sheet: String,
-    address: String,
-    row: u32,
-    col: u32,
-    data_type: String,
-    value: String,
-    formula: Option<String>,
+fn app() -> Router {
+    Router::new()
+        .route("/convert", post(convert_handler))
+        .route("/restore", post(restore_handler))
 }
 
-#[derive(Serialize)]
-struct MergedRange {
-    sheet: String,
-    start: String,
-    end: String,
+/// テキストフィールドを読み取る。UTF-8 でなければ 400 を返す
+async fn text_field(field: Field<'_>, name: &'static str) -> Result<String, AppError> {
+    let data = field.bytes().await?;
+    String::from_utf8(data.to_vec()).map_err(|e| AppError::InvalidField {
+        field: name,
+        message: e.to_string(),
+    })
 }
 
-#[tokio::main]
-async fn main() {
-    let app = Router::new().route("/convert", post(convert_handler));
+/// `true` / `false`（`1` / `0`、空は false）のフィールドを読み取る
+async fn flag_field(field: Field<'_>, name: &'static str) -> Result<bool, AppError> {
+    match text_field(field, name)
+        .await?
+        .trim()
+        .to_lowercase()
+        .as_str()
+    {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" | "" => Ok(false),
+        other => Err(AppError::InvalidField {
+            field: name,
+            message: format!("expected true or false, got '{}'", other),
+        }),
+    }
+}
 
-    let listener = TcpListener::bind("0.0.0.0:8080")
+/// パース・書き出しはブロッキング処理なので専用スレッドで実行し、
+/// 依存クレート内部の panic もエラー応答に変換する
+async fn run_blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
         .await
-        .expect("Failed to bind address");
-    let addr = listener.local_addr().unwrap();
-    println!("Listening on http://{}", addr);
-
-    axum::serve(listener, app).await.unwrap();
+        .map_err(|e| AppError::Internal(e.to_string()))?
 }
 
-async fn convert_handler(mut multipart: Multipart) -> impl IntoResponse {
+async fn convert_handler(mut multipart: Multipart) -> Result<Response, AppError> {
     let mut format_opt: Option<String> = None;
-    let mut file_bytes = Vec::new();
+    let mut input_format_opt: Option<String> = None;
+    let mut value_mode_opt: Option<String> = None;
+    let mut table_mode_opt: Option<String> = None;
+    let mut media_mode_opt: Option<String> = None;
+    let mut include_styles = false;
+    let mut include_password_hashes = false;
+    let mut file_opt: Option<Vec<u8>> = None;
     let mut filename_opt: Option<String> = None;
 
-    while let Ok(Some(field)) = multipart.next_field().await {
+    while let Some(field) = multipart.next_field().await? {
         match field.name() {
-            Some("format") => {
-                let data = field.bytes().await.unwrap();
-                format_opt = Some(String::from_utf8(data.to_vec()).unwrap());
+            Some("format") => format_opt = Some(text_field(field, "format").await?),
+            Some("input_format") => {
+                input_format_opt = Some(text_field(field, "input_format").await?);
+            }
+            Some("value_mode") => {
+                value_mode_opt = Some(text_field(field, "value_mode").await?);
+            }
+            Some("table_mode") => {
+                table_mode_opt = Some(text_field(field, "table_mode").await?);
+            }
+            Some("include_styles") => {
+                include_styles = flag_field(field, "include_styles").await?;
+            }
+            Some("include_password_hashes") => {
+                include_password_hashes = flag_field(field, "include_password_hashes").await?;
+            }
+            Some("include_media") => {
+                media_mode_opt = Some(text_field(field, "include_media").await?);
             }
             Some("file") => {
                 filename_opt = field.file_name().map(ToString::to_string);
-                let data = field.bytes().await.unwrap();
-                file_bytes = data.to_vec();
+                let data = field.bytes().await?;
+                file_opt = Some(data.to_vec());
             }
             _ => {}
         }
     }
 
-    let format = match format_opt {
-        Some(f) => f,
-        None => return (StatusCode::BAD_REQUEST, "Missing 'format'").into_response(),
+    let format = format_opt.ok_or(AppError::MissingField("format"))?;
+    let input_format = match input_format_opt.as_deref().map(str::trim) {
+        None | Some("") | Some("auto") => None,
+        Some(f) => Some(SourceFormat::from_name(&f.to_lowercase()).ok_or_else(|| {
+            AppError::UnsupportedFormat {
+                field: "input_format",
+                value: f.to_string(),
+            }
+        })?),
     };
-    let filename = match filename_opt {
-        Some(f) => f,
-        None => return (StatusCode::BAD_REQUEST, "Missing file name").into_response(),
+    let file_bytes = file_opt.ok_or(AppError::MissingField("file"))?;
+    // 空のファイルは内容から形式を判別できず、空の CSV として読まれてしまう
+    if file_bytes.is_empty() {
+        return Err(AppError::InvalidDocument {
+            format: input_format.map_or("input", |f| f.as_str()).to_string(),
+            message: "file is empty".to_string(),
+            location: None,
+        });
+    }
+
+    let value_mode = match value_mode_opt.as_deref().map(str::trim) {
+        None | Some("") => ValueMode::default(),
+        Some(m) => {
+            ValueMode::from_name(&m.to_lowercase()).ok_or_else(|| AppError::UnsupportedFormat {
+                field: "value_mode",
+                value: m.to_string(),
+            })?
+        }
     };
 
-    let workbook = match parse_workbook(&file_bytes, &filename) {
-        Ok(wb) => wb,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    let table_mode = match table_mode_opt.as_deref().map(str::trim) {
+        None | Some("") => TableMode::default(),
+        Some(m) => {
+            TableMode::from_name(&m.to_lowercase()).ok_or_else(|| AppError::UnsupportedFormat {
+                field: "table_mode",
+                value: m.to_string(),
+            })?
+        }
     };
+    let media_mode = match media_mode_opt.as_deref().map(str::trim) {
+        None | Some("") => MediaMode::default(),
+        Some(m) => {
+            MediaMode::from_name(&m.to_lowercase()).ok_or_else(|| AppError::UnsupportedFormat {
+                field: "include_media",
+                value: m.to_string(),
+            })?
+        }
+    };
+    // 見出しをキーにした行オブジェクトは XML の要素名や SQL の固定スキーマに載らない
+    if table_mode == TableMode::Rows && !matches!(format.as_str(), "json" | "yaml") {
+        return Err(AppError::InvalidField {
+            field: "table_mode",
+            message: format!("rows is only available for json or yaml, not '{}'", format),
+        });
+    }
 
-    let body = match format.as_str() {
-        "json" => serde_json::to_string_pretty(&workbook).unwrap(),
-        "yaml" => serde_yaml::to_string(&workbook).unwrap(),
-        "xml" => serde_xml_rs::to_string(&workbook).unwrap(),
-        "sql" => to_sql(&workbook),
-        _ => return (StatusCode::BAD_REQUEST, "Unsupported format").into_response(),
+    let options = ParseOptions {
+        input_format,
+        include_styles,
+        include_media: media_mode == MediaMode::Inline,
+        include_password_hashes,
     };
+    // ZIP 出力では画像を元のファイルから読み直すので、入力を返してもらう
+    let (workbook, file_bytes) = run_blocking(move || {
+        let workbook = parse_workbook(&file_bytes, filename_opt.as_deref(), options)?;
+        Ok((workbook, file_bytes))
+    })
+    .await?;
 
-    let content_type = match format.as_str() {
-        "json" => "application/json",
-        "yaml" => "application/x-yaml",
-        "xml" => "application/xml",
-        "sql" => "text/plain",
-        _ => "text/plain",
+    let document_name = format!("workbook.{}", format);
+    // SQL の value 列は TEXT 固定なので value_mode の影響を受けない
+    let (body, content_type) = match format.as_str() {
+        "sql" => (to_sql(&workbook), "text/plain"),
+        _ => serialize(
+            &WorkbookOutput {
+                workbook: &workbook,
+                value_mode,
+                table_mode,
+            },
+            format,
+        )?,
     };
 
-    ([("Content-Type", content_type)], body).into_response()
+    if media_mode == MediaMode::Zip {
+        let archive = run_blocking(move || {
+            media_archive(
+                &document_name,
+                body.as_bytes(),
+                &file_bytes,
+                &workbook.drawings,
+            )
+            .map_err(|e| AppError::Serialization(e.to_string()))
+        })
+        .await?;
+        return Ok((
+            [
+                ("Content-Type", "application/zip"),
+                (
+                    "Content-Disposition",
+                    "attachment; filename=\"converted.zip\"",
+                ),
+            ],
+            archive,
+        )
+            .into_response());
+    }
+
+    Ok(([("Content-Type", content_type)], body).into_response())
 }
 
-fn parse_workbook(bytes: &[u8], filename: &str) -> Result<Workbook, String> {
-    if filename.to_lowercase().ends_with(".csv") {
-        parse_csv(bytes)
-    } else {
-        parse_excel(bytes)
+fn serialize<T: Serialize>(value: &T, format: String) -> Result<(String, &'static str), AppError> {
+    match format.as_str() {
+        "json" => Ok((
+            serde_json::to_string_pretty(value)
+                .map_err(|e| AppError::Serialization(e.to_string()))?,
+            "application/json",
+        )),
+        "yaml" => Ok((
+            serde_yaml::to_string(value).map_err(|e| AppError::Serialization(e.to_string()))?,
+            "application/x-yaml",
+        )),
+        "xml" => Ok((
+            serde_xml_rs::to_string(value).map_err(|e| AppError::Serialization(e.to_string()))?,
+            "application/xml",
+        )),
+        _ => Err(AppError::UnsupportedFormat {
+            field: "format",
+            value: format,
+        }),
     }
 }
 
-fn parse_csv(bytes: &[u8]) -> Result<Workbook, String> {
-    let mut rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .from_reader(Cursor::new(bytes));
-    let mut cells = Vec::new();
-
-    for (row_idx, record) in rdr.records().enumerate() {
-        let record = record.map_err(|e| e.to_string())?;
-        for (col_idx, field) in record.iter().enumerate() {
-            let address = format!(
-                "{}{}",
-                col_to_letter((col_idx + 1) as u32),
-                row_idx as u32 + 1
-            );
-            cells.push(CellData {
-                sheet: "Sheet1".into(),
-                address: address.clone(),
-                row: row_idx as u32 + 1,
-                col: col_idx as u32 + 1,
-                data_type: "String".into(),
-                value: field.to_string(),
-                formula: None,
-            });
-        }
-    }
-
-    Ok(Workbook {
-        sheets: vec![SheetMetadata {
-            name: "Sheet1".into(),
-            index: 0,
-            hidden: false,
-        }],
-        cells,
-        merged_ranges: Vec::new(),
+async fn restore_handler(mut multipart: Multipart) -> Result<Response, AppError> {
+    let mut format_opt: Option<String> = None;
+    let mut file_opt: Option<Vec<u8>> = None;
+
+    while let Some(field) = multipart.next_field().await? {
+        match field.name() {
+            Some("format") => format_opt = Some(text_field(field, "format").await?),
+            Some("file") => {
+                let data = field.bytes().await?;
+                file_opt = Some(data.to_vec());
+            }
+            _ => {}
+        }
+    }
+
+    let format = format_opt.ok_or(AppError::MissingField("format"))?;
+    let file_bytes = file_opt.ok_or(AppError::MissingField("file"))?;
+    let (body, dropped_replies, dropped_passwords) = run_blocking(move || {
+        let workbook = parse_document(&file_bytes, &format)?;
+        // メモはスレッド形式を持てないため、コメントの返信は復元されない
+        let dropped_replies: usize = workbook.comments.iter().map(|c| c.replies.len()).sum();
+        // ハッシュを含まない保護はパスワード無しで復元される
+        let dropped_passwords = workbook
+            .protection
+            .iter()
+            .map(|p| (p.has_password, p.password.is_some()))
+            .chain(
+                workbook
+                    .sheets
+                    .iter()
+                    .filter_map(|s| s.protection.as_ref())
+                    .map(|p| (p.has_password, p.password.is_some())),
+            )
+            .filter(|&(has_password, has_hash)| has_password && !has_hash)
+            .count();
+        Ok((restore_xlsx(&workbook)?, dropped_replies, dropped_passwords))
     })
+    .await?;
+
+    let mut response = (
+        [
+            (
+                "Content-Type",
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ),
+            (
+                "Content-Disposition",
+                "attachment; filename=\"restored.xlsx\"",
+            ),
+        ],
+        body,
+    )
+        .into_response();
+    if dropped_replies > 0 {
+        response
+            .headers_mut()
+            .insert("X-Dropped-Comment-Replies", dropped_replies.into());
+    }
+    if dropped_passwords > 0 {
+        response
+            .headers_mut()
+            .insert("X-Dropped-Password-Protection", dropped_passwords.into());
+    }
+    Ok(response)
 }
 
-fn parse_excel(bytes: &[u8]) -> Result<Workbook, String> {
-    let mut excel =
-        Xlsx::new(Cursor::new(bytes)).map_err(|e| format!("Excel open error: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::app;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{HeaderMap, Request, StatusCode};
+    use tower::ServiceExt;
+
+    const BOUNDARY: &str = "re-excel-test-boundary";
+
+    fn multipart_body(fields: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (name, filename, data) in fields {
+            body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+            match filename {
+                Some(f) => body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\r\n",
+                        name, f
+                    )
+                    .as_bytes(),
+                ),
+                None => body.extend_from_slice(
+                    format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name).as_bytes(),
+                ),
+            }
+            body.extend_from_slice(data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+        body
+    }
+
+    async fn post_raw(path: &str, body: Vec<u8>) -> (StatusCode, Vec<u8>) {
+        let (status, _, bytes) = post_with_headers(path, body).await;
+        (status, bytes)
+    }
+
+    async fn post_with_headers(path: &str, body: Vec<u8>) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let request = Request::post(path)
+            .header(
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, headers, bytes.to_vec())
+    }
+
+    async fn post(path: &str, body: Vec<u8>) -> (StatusCode, serde_json::Value) {
+        let (status, bytes) = post_raw(path, body).await;
+        let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        (status, json)
+    }
+
+    fn sample_xlsx() -> Vec<u8> {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        ws.write_string(0, 0, "name").unwrap();
+        ws.write_number(1, 0, 1.5).unwrap();
+        ws.write_formula(1, 1, "=A2*2").unwrap();
+        wb.save_to_buffer().unwrap()
+    }
+
+    /// rust_xlsxwriter が作れない部品（ピボットテーブルなど）をパッケージに追加する
+    fn with_parts(xlsx: &[u8], parts: &[(&str, &str)]) -> Vec<u8> {
+        use std::io::Write;
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(xlsx)).unwrap();
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for i in 0..archive.len() {
+            writer
+                .raw_copy_file(archive.by_index_raw(i).unwrap())
+                .unwrap();
+        }
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (path, xml) in parts {
+            writer.start_file(*path, options).unwrap();
+            writer.write_all(xml.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// rust_xlsxwriter が書けない要素（x14 の拡張など）を既存の部品に差し込む
+    fn patch_part(xlsx: &[u8], path: &str, from: &str, to: &str) -> Vec<u8> {
+        let mut package = crate::ooxml::Package::open(xlsx).unwrap();
+        let xml = String::from_utf8(package.raw_part(path).unwrap()).unwrap();
+        assert!(xml.contains(from), "{} has no {}", path, from);
+        let xml = xml.replacen(from, to, 1);
+        crate::ooxml::replace_parts(xlsx, &[(path.to_string(), xml.into_bytes())]).unwrap()
+    }
+
+    /// 部品を並べただけのパッケージ（.ods などの判別用）
+    fn zip_of(parts: &[(&str, &str)]) -> Vec<u8> {
+        use std::io::Write;
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (path, data) in parts {
+            writer.start_file(*path, options).unwrap();
+            writer.write_all(data.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// 変換結果から .xlsx を復元し、もう一度変換する
+    async fn restore_and_convert(document: &[u8], format: &str) -> serde_json::Value {
+        let body = multipart_body(&[
+            ("format", None, format.as_bytes()),
+            ("file", None, document),
+        ]);
+        let (status, restored) = post_raw("/restore", body).await;
+        assert_eq!(
+            status,
+            StatusCode::OK,
+            "{}",
+            String::from_utf8_lossy(&restored)
+        );
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", Some("restored.xlsx"), &restored),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        json
+    }
+
+    /// 外部クレートに頼らない決定的な疑似乱数（xorshift64）
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn converts_valid_upload() {
+        let xlsx = sample_xlsx();
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["source_format"], "xlsx");
+        assert_eq!(json["date1904"], false);
+        assert_eq!(json["cells"][2]["formula"], "A2*2");
+    }
+
+    #[tokio::test]
+    async fn restores_converted_workbooks() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet().set_name("Data").unwrap();
+        ws.write_string(0, 0, "name").unwrap();
+        ws.write_number(0, 1, 2.5).unwrap();
+        ws.write_boolean(0, 2, true).unwrap();
+        wb.add_worksheet().set_name("Empty").unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
 
-    let mut sheets = Vec::new();
-    let mut cells = Vec::new();
-    let merged_ranges = Vec::new();
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, json) = post("/convert", body).await;
+        for format in ["json", "yaml", "xml", "sql"] {
+            let body = multipart_body(&[
+                ("format", None, format.as_bytes()),
+                ("file", Some("a.xlsx"), &xlsx),
+            ]);
+            let (status, document) = post_raw("/convert", body).await;
+            assert_eq!(status, StatusCode::OK, "{}", format);
+            let restored = restore_and_convert(&document, format).await;
+            assert_eq!(restored["sheets"], json["sheets"], "{}", format);
+            assert_eq!(restored["cells"], json["cells"], "{}", format);
+        }
 
-    for (idx, name) in excel.sheet_names().iter().enumerate() {
-        sheets.push(SheetMetadata {
-            name: name.clone(),
-            index: idx,
-            hidden: false,
+        // 数式の無いエラー値は、数式を付けずにエラー値のまま復元する
+        let document = serde_json::json!({
+            "sheets": [{ "name": "Sheet1", "index": 0, "hidden": false }],
+            "cells": [{
+                "sheet": "Sheet1", "address": "A1", "row": 1, "col": 1,
+                "data_type": "Error", "value": "Div0",
+            }],
         });
+        let restored = restore_and_convert(document.to_string().as_bytes(), "json").await;
+        assert_eq!(restored["cells"][0]["data_type"], "Error");
+        assert_eq!(restored["cells"][0]["value"], "Div0");
+        assert_eq!(restored["cells"][0]["formula"], serde_json::Value::Null);
+    }
 
-        let range = excel
-            .worksheet_range(name)
-            .map_err(|e| format!("Error reading sheet {}: {}", name, e))?;
-
-        for (r, c, v) in range.cells() {
-            let address = format!("{}{}", col_to_letter(c as u32 + 1), r as u32 + 1);
-            let (data_type, value, formula) = match *v {
-                Data::Empty => continue,
-                Data::String(ref s) => ("String".to_string(), s.clone(), None),
-                Data::Float(f) => ("Number".to_string(), f.to_string(), None),
-                Data::Int(i) => ("Number".to_string(), i.to_string(), None),
-                Data::Bool(b) => ("Boolean".to_string(), b.to_string(), None),
-                Data::Error(ref e) => ("Error".to_string(), format!("{:?}", e), None),
-                Data::DateTime(dt) => ("DateTime".to_string(), dt.to_string(), None),
-                Data::DateTimeIso(ref s) => ("DateTimeIso".to_string(), s.clone(), None),
-                Data::DurationIso(ref s) => ("DurationIso".to_string(), s.clone(), None),
-            };
-            cells.push(CellData {
-                sheet: name.clone(),
-                address,
-                row: r as u32 + 1,
-                col: c as u32 + 1,
-                data_type,
-                value,
-                formula,
-            });
-        }
-    }
-
-    Ok(Workbook {
-        sheets,
-        cells,
-        merged_ranges,
-    })
-}
+    #[tokio::test]
+    async fn keeps_formulas_and_cached_results() {
+        let xlsx = sample_xlsx();
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, json) = post("/convert", body).await;
+        let formula = &json["cells"][2];
+        assert_eq!(formula["address"], "B2");
+        assert_eq!(formula["formula"], "A2*2");
+        // 数式の無いセルも従来どおり `formula: null` を出力する
+        assert_eq!(
+            json["cells"][0].get("formula"),
+            Some(&serde_json::Value::Null)
+        );
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("value_mode", None, b"typed"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (_, typed) = post("/convert", body).await;
+        assert_eq!(
+            typed["cells"][0].get("formula"),
+            Some(&serde_json::Value::Null)
+        );
 
-fn col_to_letter(mut col: u32) -> String {
-    let mut s = String::new();
-    while col > 0 {
-        let rem = (col - 1) % 26;
-        s.insert(0, (b'A' + rem as u8) as char);
-        col = (col - 1) / 26;
+        let restored = restore_and_convert(json.to_string().as_bytes(), "json").await;
+        assert_eq!(restored["cells"][2]["formula"], "A2*2");
+        assert_eq!(restored["cells"][2]["data_type"], formula["data_type"]);
+        assert_eq!(restored["cells"][2]["value"], formula["value"]);
+    }
+
+    #[tokio::test]
+    async fn round_trips_merged_ranges() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        ws.merge_range(0, 0, 1, 2, "title", &rust_xlsxwriter::Format::new())
+            .unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, json) = post("/convert", body).await;
+        assert_eq!(
+            json["merged_ranges"],
+            serde_json::json!([{ "sheet": "Sheet1", "start": "A1", "end": "C2" }])
+        );
+        let restored = restore_and_convert(json.to_string().as_bytes(), "json").await;
+        assert_eq!(restored["merged_ranges"], json["merged_ranges"]);
+        assert_eq!(restored["cells"][0]["value"], "title");
+    }
+
+    #[tokio::test]
+    async fn reports_sheet_visibility() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        wb.add_worksheet().set_name("Visible").unwrap();
+        wb.add_worksheet()
+            .set_name("Hidden")
+            .unwrap()
+            .set_hidden(true);
+        wb.add_worksheet()
+            .set_name("Secret")
+            .unwrap()
+            .set_very_hidden(true);
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, json) = post("/convert", body).await;
+        let visibility: Vec<_> = json["sheets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| (s["visibility"].clone(), s["hidden"].clone()))
+            .collect();
+        assert_eq!(
+            visibility,
+            [
+                ("visible".into(), false.into()),
+                ("hidden".into(), true.into()),
+                ("very_hidden".into(), true.into()),
+            ]
+        );
+        assert_eq!(json["sheets"][2]["sheet_type"], "worksheet");
+
+        let restored = restore_and_convert(json.to_string().as_bytes(), "json").await;
+        assert_eq!(restored["sheets"], json["sheets"]);
+    }
+
+    #[tokio::test]
+    async fn detects_input_format_from_content() {
+        let ods = zip_of(&[
+            ("mimetype", "application/vnd.oasis.opendocument.spreadsheet"),
+            (
+                "META-INF/manifest.xml",
+                r#"<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0"><manifest:file-entry manifest:full-path="/" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/></manifest:manifest>"#,
+            ),
+            (
+                "content.xml",
+                r#"<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0"><office:body><office:spreadsheet><table:table table:name="Tab"><table:table-row><table:table-cell office:value-type="string"><text:p>hello</text:p></table:table-cell></table:table-row></table:table></office:spreadsheet></office:body></office:document-content>"#,
+            ),
+        ]);
+        // 拡張子に関係なく中身で判別する
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &ods)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK, "{}", json);
+        assert_eq!(json["source_format"], "ods");
+        assert!(json.get("date1904").is_none());
+        assert_eq!(json["cells"][0]["value"], "hello");
+
+        // 中身が壊れていれば判別した形式のエラーになる
+        let mut xls = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+        xls.resize(512, 0);
+        let xlsb = zip_of(&[("xl/workbook.bin", "")]);
+        for (data, format) in [(xls, "xls"), (xlsb, "xlsb")] {
+            let body = multipart_body(&[("format", None, b"json"), ("file", None, &data)]);
+            let (status, json) = post("/convert", body).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", format);
+            assert_eq!(json["error"], "CorruptContainer");
+            assert!(
+                json["message"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with(&format!("Cannot open {} file", format)),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn input_format_overrides_detection() {
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("input_format", None, b"CSV"),
+            ("file", None, b"a,1\n"),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["source_format"], "csv");
+        assert!(json.get("date1904").is_none());
+        assert_eq!(json["cells"][1]["value"], "1");
+
+        // .xlsx を csv として読ませれば、判別結果ではなく指定が使われる
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("input_format", None, b"xls"),
+            ("file", Some("a.xlsx"), &sample_xlsx()),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json["error"], "CorruptContainer");
+
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("input_format", None, b"numbers"),
+            ("file", None, b"a\n"),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "UnsupportedFormat");
+    }
+
+    #[tokio::test]
+    async fn rejects_non_utf8_csv() {
+        // 「名前,値」を Shift_JIS で符号化したもの
+        let shift_jis = b"\x96\xbc\x91\x4f,\x92\x6c\r\n";
+        let body = multipart_body(&[("format", None, b"json"), ("file", None, shift_jis)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(json["error"], "InvalidEncoding");
+        assert_eq!(json["message"], "CSV must be UTF-8");
+
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("input_format", None, b"csv"),
+            ("file", None, b"a,b\r\nc,\x92\x6c\r\n"),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(json["error"], "InvalidEncoding");
+        assert_eq!(
+            json["details"],
+            serde_json::json!([{ "sheet": "Sheet1", "cell": "B2" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_carry_codes_and_locations() {
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", Some("a.bin"), b"\0\x01"),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(json["error"], "UnrecognizedInput");
+        assert!(json["message"].as_str().unwrap().contains("a.bin"));
+
+        let body = multipart_body(&[("format", None, b"toml"), ("file", None, b"a\n")]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "UnsupportedFormat");
+
+        let document = r#"{"sheets":[],"cells":[{"sheet":"S","address":"A0","row":0,"col":1,"data_type":"String","value":"x"}]}"#;
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", None, document.as_bytes()),
+        ]);
+        let (status, json) = post("/restore", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "ParseError");
+        assert_eq!(
+            json["details"],
+            serde_json::json!([{ "sheet": "S", "cell": "A0" }])
+        );
+
+        let document = r#"{"sheets":[{"name":"A","index":0,"hidden":false},{"name":"A","index":1,"hidden":false}]}"#;
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", None, document.as_bytes()),
+        ]);
+        let (status, json) = post("/restore", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json["error"], "RestoreError");
+    }
+
+    #[tokio::test]
+    async fn converts_typed_values() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        let date = rust_xlsxwriter::Format::new().set_num_format("yyyy-mm-dd");
+        ws.write_string(0, 0, "name").unwrap();
+        ws.write_number(0, 1, 1.5).unwrap();
+        ws.write_number(0, 2, 1e-7).unwrap();
+        ws.write_boolean(0, 3, true).unwrap();
+        ws.write_formula(
+            0,
+            4,
+            rust_xlsxwriter::Formula::new("=1/0").set_result("#DIV/0!"),
+        )
+        .unwrap();
+        ws.write_number_with_format(0, 5, 45366.0, &date).unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("value_mode", None, b"typed"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let values: Vec<_> = json["cells"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["value"].clone())
+            .collect();
+        assert_eq!(
+            values,
+            serde_json::json!([
+                { "type": "string", "value": "name" },
+                { "type": "number", "value": 1.5 },
+                { "type": "number", "value": 1e-7 },
+                { "type": "bool", "value": true },
+                { "type": "error", "value": "#DIV/0!" },
+                { "type": "date", "value": "2024-03-15" },
+            ])
+            .as_array()
+            .unwrap()
+            .clone()
+        );
+        assert_eq!(json["cells"][1]["data_type"], "Number");
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, strings) = post("/convert", body).await;
+        for format in ["json", "yaml", "xml"] {
+            let body = multipart_body(&[
+                ("format", None, format.as_bytes()),
+                ("value_mode", None, b"typed"),
+                ("file", Some("a.xlsx"), &xlsx),
+            ]);
+            let (status, document) = post_raw("/convert", body).await;
+            assert_eq!(status, StatusCode::OK);
+            let restored = restore_and_convert(&document, format).await;
+            assert_eq!(restored["cells"], strings["cells"], "{}", format);
+        }
+    }
+
+    #[tokio::test]
+    async fn includes_styles_on_request() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        let bold = rust_xlsxwriter::Format::new().set_bold();
+        ws.write_string_with_format(0, 0, "head", &bold).unwrap();
+        ws.write_string_with_format(0, 1, "head", &bold).unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, json) = post("/convert", body).await;
+        assert!(json.get("styles").is_none());
+        assert!(json["cells"][0].get("style_id").is_none());
+
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("include_styles", None, b"true"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["styles"].as_array().unwrap().len(), 1);
+        assert_eq!(json["styles"][0]["font"]["bold"], true);
+        assert_eq!(json["cells"][0]["style_id"], 0);
+        assert_eq!(json["cells"][1]["style_id"], 0);
+    }
+
+    #[tokio::test]
+    async fn restores_comment_authors() {
+        let doc = serde_json::json!({
+            "sheets": [{ "name": "Sheet1", "index": 0, "hidden": false }],
+            "cells": [],
+            "comments": [
+                { "sheet": "Sheet1", "address": "A1", "author": "Zoe", "text": "first" },
+                { "sheet": "Sheet1", "address": "B2", "author": "Adam", "text": "second" },
+                {
+                    "sheet": "Sheet1", "address": "C3", "text": "anonymous",
+                    "replies": [{ "author": "Zoe", "text": "reply" }, { "text": "again" }],
+                },
+            ],
+        });
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", None, doc.to_string().as_bytes()),
+        ]);
+        let (status, headers, xlsx) = post_with_headers("/restore", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["X-Dropped-Comment-Replies"], "2");
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let authors: Vec<_> = json["comments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| {
+                (
+                    c["address"].as_str().unwrap(),
+                    c["author"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(authors, [("A1", "Zoe"), ("B2", "Adam"), ("C3", "Author")]);
+    }
+
+    #[tokio::test]
+    async fn restores_hyperlinks_with_cell_text() {
+        let doc = serde_json::json!({
+            "sheets": [{ "name": "Sheet1", "index": 0, "hidden": false }],
+            "cells": [{
+                "sheet": "Sheet1", "address": "A1", "row": 1, "col": 1,
+                "data_type": "String", "value": "Docs",
+            }],
+            "hyperlinks": [
+                {
+                    "sheet": "Sheet1", "range": "A1", "target": "https://example.com/docs",
+                    "internal": false,
+                },
+                {
+                    "sheet": "Sheet1", "range": "B2", "target": "https://example.com/",
+                    "tooltip": "tip", "internal": false,
+                },
+                { "sheet": "Sheet1", "range": "C3", "target": "Sheet1!A1", "internal": true },
+            ],
+        });
+        let restored = restore_and_convert(doc.to_string().as_bytes(), "json").await;
+        assert_eq!(restored["hyperlinks"], doc["hyperlinks"]);
+        // リンクだけのセルに URL が値として書かれない
+        let values: Vec<_> = restored["cells"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["address"].as_str().unwrap(), c["value"].as_str().unwrap()))
+            .collect();
+        assert_eq!(values, [("A1", "Docs")]);
     }
-    s
-}
 
-fn to_sql(wb: &Workbook) -> String {
-    let mut sql = String::new();
-    sql.push_str(
-        "CREATE TABLE cell_data (sheet TEXT, address TEXT, row INTEGER, col INTEGER, data_type TEXT, value TEXT, formula TEXT);\n",
-    );
-    for cell in &wb.cells {
-        let formula_value = match &cell.formula {
-            Some(f) => format!("'{}'", f.replace('\'', "''")),
-            None => "NULL".into(),
+    #[tokio::test]
+    async fn round_trips_defined_names() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet().set_name("Rates").unwrap();
+        ws.write_number(0, 1, 0.1).unwrap();
+        wb.add_worksheet().set_name("Input").unwrap();
+        wb.define_name("TaxRate", "=Rates!$B$1").unwrap();
+        wb.define_name("Input!Local", "=Input!$A$1:$A$3").unwrap();
+        wb.define_name("Helper", "=Rates!$C$1").unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+        // rust_xlsxwriter は非表示の名前を書けない
+        let xlsx = patch_part(
+            &xlsx,
+            "xl/workbook.xml",
+            r#"<definedName name="Helper">"#,
+            r#"<definedName name="Helper" hidden="1">"#,
+        );
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let name = |json: &serde_json::Value, name: &str| {
+            json["defined_names"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|n| n["name"] == name)
+                .cloned()
+                .unwrap()
         };
-        sql.push_str(&format!(
-            "INSERT INTO cell_data VALUES ('{}','{}',{},{},'{}','{}',{});\n",
-            cell.sheet.replace('\'', "''"),
-            cell.address,
-            cell.row,
-            cell.col,
-            cell.data_type.replace('\'', "''"),
-            cell.value.replace('\'', "''"),
-            formula_value
+        assert_eq!(
+            name(&json, "TaxRate"),
+            serde_json::json!({ "name": "TaxRate", "refers_to": "Rates!$B$1", "hidden": false })
+        );
+        assert_eq!(
+            name(&json, "Local"),
+            serde_json::json!({
+                "name": "Local",
+                "scope": "Input",
+                "refers_to": "Input!$A$1:$A$3",
+                "hidden": false,
+            })
+        );
+        assert_eq!(name(&json, "Helper")["hidden"], true);
+
+        let body = multipart_body(&[("format", None, b"sql"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, sql) = post_raw("/convert", body).await;
+        let sql = String::from_utf8(sql).unwrap();
+        for row in [
+            "('TaxRate',NULL,'Rates!$B$1',0)",
+            "('Local','Input','Input!$A$1:$A$3',0)",
+            "('Helper',NULL,'Rates!$C$1',1)",
+        ] {
+            assert!(
+                sql.contains(&format!("INSERT INTO defined_name VALUES {};", row)),
+                "{}",
+                row
+            );
+        }
+
+        for format in ["json", "sql"] {
+            let body = multipart_body(&[
+                ("format", None, format.as_bytes()),
+                ("file", Some("a.xlsx"), &xlsx),
+            ]);
+            let (_, document) = post_raw("/convert", body).await;
+            let restored = restore_and_convert(&document, format).await;
+            for n in ["TaxRate", "Local", "Helper"] {
+                assert_eq!(name(&restored, n), name(&json, n), "{} {}", format, n);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trips_rich_text_and_phonetic() {
+        let doc = serde_json::json!({
+            "sheets": [{ "name": "Sheet1", "index": 0, "hidden": false }],
+            "cells": [{
+                "sheet": "Sheet1", "address": "A1", "row": 1, "col": 1,
+                "data_type": "String", "value": "東京タワー",
+                "runs": [
+                    { "text": "東京" },
+                    { "text": "タワー", "font": { "bold": true, "color": "#FF0000" } },
+                ],
+                "phonetic": [{ "text": "トウキョウ", "start": 0, "end": 2 }],
+            }, {
+                // value だけが編集され、書式付き区間と食い違っているセル
+                "sheet": "Sheet1", "address": "A2", "row": 2, "col": 1,
+                "data_type": "String", "value": "大阪城",
+                "runs": [{ "text": "大阪" }, { "text": "タワー", "font": { "bold": true } }],
+            }],
+        });
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", None, doc.to_string().as_bytes()),
+        ]);
+        let (status, xlsx) = post_raw("/restore", body).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let cell = &json["cells"][0];
+        assert_eq!(cell["value"], "東京タワー");
+        assert_eq!(cell["runs"][0]["text"], "東京");
+        assert_eq!(cell["runs"][1]["font"]["bold"], true);
+        assert_eq!(cell["runs"][1]["font"]["color"], "#FF0000");
+        assert_eq!(cell["phonetic"], doc["cells"][0]["phonetic"]);
+        let edited = &json["cells"][1];
+        assert_eq!(edited["value"], "大阪城");
+        assert!(edited.get("runs").is_none());
+
+        // 同じ本文は 1 つの共有文字列になるため、読みが食い違うセルは復元できない
+        let conflicting = serde_json::json!({
+            "sheets": [{ "name": "Sheet1", "index": 0, "hidden": false }],
+            "cells": [
+                { "sheet": "Sheet1", "address": "A1", "row": 1, "col": 1,
+                  "data_type": "String", "value": "上野",
+                  "phonetic": [{ "text": "ウエノ", "start": 0, "end": 2 }] },
+                { "sheet": "Sheet1", "address": "A2", "row": 2, "col": 1,
+                  "data_type": "String", "value": "上野",
+                  "phonetic": [{ "text": "カミノ", "start": 0, "end": 2 }] },
+            ],
+        });
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", None, conflicting.to_string().as_bytes()),
+        ]);
+        let (status, json) = post("/restore", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json["error"], "RestoreError");
+    }
+
+    #[tokio::test]
+    async fn lists_drawings_with_media() {
+        // 1x1 の PNG
+        const PNG: &[u8] = &[
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00,
+            0x00, 0x1F, 0x15, 0xC4, 0x89, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0x9C, 0x63, 0xF8, 0xCF, 0xC0, 0xF0, 0x1F, 0x00, 0x05, 0x00, 0x01, 0xFF, 0x89, 0x99,
+            0x3D, 0x1D, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+        ];
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        let image = rust_xlsxwriter::Image::new_from_buffer(PNG)
+            .unwrap()
+            .set_width(40)
+            .set_height(30)
+            .set_alt_text("inspection photo");
+        ws.insert_image_with_offset(1, 2, &image, 5, 0).unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let drawing = &json["drawings"][0];
+        assert_eq!(drawing["kind"], "image");
+        assert_eq!(
+            drawing["range"].as_str().unwrap().split(':').next(),
+            Some("C2")
+        );
+        assert_eq!(drawing["x_offset"], 5);
+        assert_eq!(
+            (drawing["width"].clone(), drawing["height"].clone()),
+            (40.into(), 30.into())
+        );
+        assert_eq!(drawing["alt_text"], "inspection photo");
+        assert_eq!(drawing["media_type"], "image/png");
+        assert!(drawing.get("data").is_none());
+
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("include_media", None, b"inline"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (_, json) = post("/convert", body).await;
+        assert!(
+            json["drawings"][0]["data"]
+                .as_str()
+                .unwrap()
+                .starts_with("iVBORw0KGgo")
+        );
+
+        // 画像の中身を含む出力から復元できる
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", None, json.to_string().as_bytes()),
+        ]);
+        let (status, restored) = post_raw("/restore", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", Some("a.xlsx"), &restored),
+        ]);
+        let (_, mut json) = post("/convert", body).await;
+        assert_eq!(json["drawings"][0]["alt_text"], "inspection photo");
+
+        // 壊れた base64 は黙って捨てずに入力エラーにする
+        json["drawings"][0]["data"] = "iVBORw0KGgo".into();
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", None, json.to_string().as_bytes()),
+        ]);
+        let (status, json) = post("/restore", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "ParseError");
+        assert_eq!(
+            json["details"],
+            serde_json::json!([{ "sheet": "Sheet1", "cell": "C2" }])
+        );
+
+        let body = multipart_body(&[
+            ("format", None, b"yaml"),
+            ("include_media", None, b"zip"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (status, archive) = post_raw("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        assert!(zip.by_name("workbook.yaml").is_ok());
+        let media = zip.by_name("xl/media/image1.png").unwrap();
+        assert_eq!(media.size(), PNG.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn round_trips_chart_definitions() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet().set_name("Sales").unwrap();
+        ws.write_row(0, 0, ["Month", "East", "West"]).unwrap();
+        for (row, month) in ["Jan", "Feb", "Mar"].iter().enumerate() {
+            let row = row as u32 + 1;
+            ws.write_string(row, 0, *month).unwrap();
+            ws.write_number(row, 1, row as f64 * 10.0).unwrap();
+            ws.write_number(row, 2, row as f64 * 5.0).unwrap();
+        }
+        let mut chart = rust_xlsxwriter::Chart::new(rust_xlsxwriter::ChartType::ColumnStacked);
+        chart.title().set_name("Monthly sales");
+        for col in ["B", "C"] {
+            chart
+                .add_series()
+                .set_name(format!("=Sales!${}$1", col).as_str())
+                .set_categories("=Sales!$A$2:$A$4")
+                .set_values(format!("=Sales!${col}$2:${col}$4").as_str());
+        }
+        ws.insert_chart_with_offset(1, 4, &chart, 10, 0).unwrap();
+        // マーカーのみの散布図と、線とマーカーの散布図
+        for (row, chart_type) in [
+            (20, rust_xlsxwriter::ChartType::Scatter),
+            (40, rust_xlsxwriter::ChartType::ScatterStraightWithMarkers),
+        ] {
+            let mut chart = rust_xlsxwriter::Chart::new(chart_type);
+            chart
+                .add_series()
+                .set_categories("=Sales!$B$2:$B$4")
+                .set_values("=Sales!$C$2:$C$4");
+            ws.insert_chart(row, 4, &chart).unwrap();
+        }
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["drawings"][0]["kind"], "chart");
+        let chart = &json["charts"][0];
+        assert_eq!(chart["sheet"], "Sales");
+        assert_eq!(
+            chart["range"].as_str().unwrap().split(':').next(),
+            Some("E2")
+        );
+        assert_eq!(chart["x_offset"], 10);
+        assert_eq!(chart["chart_type"], "bar");
+        assert_eq!(chart["bar_direction"], "col");
+        assert_eq!(chart["grouping"], "stacked");
+        assert_eq!(chart["title"], "Monthly sales");
+        let series = &chart["series"][1];
+        assert_eq!(series["name"], "West");
+        assert_eq!(series["name_ref"], "Sales!$C$1");
+        assert_eq!(series["categories"], "Sales!$A$2:$A$4");
+        assert_eq!(series["values"], "Sales!$C$2:$C$4");
+        let scatter = &json["charts"].as_array().unwrap()[1..];
+        assert_eq!(
+            scatter
+                .iter()
+                .map(|c| (c["chart_type"].clone(), c["style"].clone()))
+                .collect::<Vec<_>>(),
+            [
+                ("scatter".into(), "marker".into()),
+                ("scatter".into(), "lineMarker".into())
+            ]
+        );
+
+        // SQL を経由しても同じ定義のグラフを作り直せる
+        let body = multipart_body(&[("format", None, b"sql"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, sql) = post_raw("/convert", body).await;
+        let body = multipart_body(&[("format", None, b"sql"), ("file", None, &sql)]);
+        let (status, restored) = post_raw("/restore", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", Some("a.xlsx"), &restored),
+        ]);
+        let (_, restored) = post("/convert", body).await;
+        assert_eq!(restored["charts"], json["charts"]);
+    }
+
+    #[tokio::test]
+    async fn marks_pivot_table_output() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let data = wb.add_worksheet().set_name("Data").unwrap();
+        data.write_row(0, 0, ["Region", "Product", "Amount"])
+            .unwrap();
+        data.write_row(1, 0, ["East", "Tea"]).unwrap();
+        data.write_number(1, 2, 10.0).unwrap();
+        data.write_row(2, 0, ["West", "Tea"]).unwrap();
+        data.write_number(2, 2, 5.0).unwrap();
+        let report = wb.add_worksheet().set_name("Report").unwrap();
+        report
+            .write_row(2, 0, ["Row Labels", "Sum of Amount"])
+            .unwrap();
+        report.write_string(3, 0, "East").unwrap();
+        report.write_number(3, 1, 10.0).unwrap();
+        report.write_string(4, 0, "West").unwrap();
+        report.write_number(4, 1, 5.0).unwrap();
+        report.write_string(6, 0, "note").unwrap();
+        let xlsx = with_parts(
+            &wb.save_to_buffer().unwrap(),
+            &[
+                (
+                    "xl/worksheets/_rels/sheet2.xml.rels",
+                    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/pivotTable" Target="../pivotTables/pivotTable1.xml"/></Relationships>"#,
+                ),
+                (
+                    "xl/pivotTables/_rels/pivotTable1.xml.rels",
+                    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/pivotCacheDefinition" Target="../pivotCache/pivotCacheDefinition1.xml"/></Relationships>"#,
+                ),
+                (
+                    "xl/pivotTables/pivotTable1.xml",
+                    r#"<pivotTableDefinition xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" name="SalesPivot" cacheId="1" dataCaption="Values"><location ref="A3:B5" firstHeaderRow="1" firstDataRow="1" firstDataCol="1"/><pivotFields count="3"><pivotField axis="axisRow"/><pivotField axis="axisPage"/><pivotField dataField="1"/></pivotFields><rowFields count="1"><field x="0"/></rowFields><pageFields count="1"><pageField fld="1" hier="-1"/></pageFields><dataFields count="1"><dataField name="Sum of Amount" fld="2" baseField="0" baseItem="0"/></dataFields></pivotTableDefinition>"#,
+                ),
+                (
+                    "xl/pivotCache/pivotCacheDefinition1.xml",
+                    r#"<pivotCacheDefinition xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><cacheSource type="worksheet"><worksheetSource ref="A1:C3" sheet="Data"/></cacheSource><cacheFields count="3"><cacheField name="Region"/><cacheField name="Product"/><cacheField name="Amount"/></cacheFields></pivotCacheDefinition>"#,
+                ),
+            ],
+        );
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let pivot = &json["pivot_tables"][0];
+        assert_eq!(pivot["name"], "SalesPivot");
+        assert_eq!(pivot["sheet"], "Report");
+        assert_eq!(pivot["range"], "A3:B5");
+        assert_eq!(pivot["source_sheet"], "Data");
+        assert_eq!(pivot["source_range"], "A1:C3");
+        assert_eq!(pivot["row_fields"], serde_json::json!(["Region"]));
+        assert_eq!(pivot["page_fields"], serde_json::json!(["Product"]));
+        assert_eq!(pivot["data_fields"][0]["field"], "Amount");
+        assert_eq!(pivot["data_fields"][0]["function"], "sum");
+
+        let cells = json["cells"].as_array().unwrap();
+        let derived = |sheet: &str, address: &str| {
+            cells
+                .iter()
+                .find(|c| c["sheet"] == sheet && c["address"] == address)
+                .unwrap()
+                .get("pivot_table")
+                .cloned()
+        };
+        assert_eq!(derived("Report", "B4"), Some("SalesPivot".into()));
+        assert_eq!(derived("Report", "A7"), None);
+        assert_eq!(derived("Data", "C2"), None);
+
+        let body = multipart_body(&[("format", None, b"sql"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, sql) = post_raw("/convert", body).await;
+        let sql = String::from_utf8(sql).unwrap();
+        assert!(sql.contains(
+            "INSERT INTO pivot_field VALUES ('Report','SalesPivot','page',0,'Product');"
         ));
     }
-    sql
+
+    #[tokio::test]
+    async fn round_trips_protection() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        ws.write_string(0, 0, "Input").unwrap();
+        let unlocked = rust_xlsxwriter::Format::new().set_unlocked();
+        ws.write_string_with_format(1, 1, "here", &unlocked)
+            .unwrap();
+        ws.write_blank(2, 2, &unlocked).unwrap();
+        ws.protect_with_password("secret");
+        ws.protect_with_options(&rust_xlsxwriter::ProtectionOptions {
+            format_cells: true,
+            ..Default::default()
+        });
+        let xlsx = wb.save_to_buffer().unwrap();
+        let xlsx = patch_part(
+            &xlsx,
+            "xl/workbook.xml",
+            "<bookViews",
+            r#"<workbookProtection workbookAlgorithmName="SHA-512" workbookHashValue="aGFzaA==" workbookSaltValue="c2FsdA==" workbookSpinCount="100000" lockStructure="1"/><bookViews"#,
+        );
+
+        // 既定ではパスワードの有無だけを出し、書式だけの空セルも出さない
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["protection"]["has_password"], true);
+        assert!(json["protection"].get("password").is_none());
+        assert_eq!(json["sheets"][0]["protection"]["has_password"], true);
+        assert!(json["sheets"][0]["protection"].get("password").is_none());
+        assert_eq!(json["cells"].as_array().unwrap().len(), 2);
+        assert!(json["cells"][0].get("locked").is_none());
+        assert_eq!(json["cells"][1]["address"], "B2");
+        assert_eq!(json["cells"][1]["locked"], false);
+        // ハッシュが無いので、ブックとシートの保護はパスワード無しで復元される
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("file", None, json.to_string().as_bytes()),
+        ]);
+        let (status, headers, _) = post_with_headers("/restore", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["X-Dropped-Password-Protection"], "2");
+
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("include_password_hashes", None, b"true"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let workbook = &json["protection"];
+        assert_eq!(workbook["structure"], true);
+        assert_eq!(workbook["windows"], false);
+        assert_eq!(workbook["password"]["algorithm"], "SHA-512");
+        assert_eq!(workbook["password"]["spin_count"], 100000);
+        let sheet = &json["sheets"][0]["protection"];
+        assert_eq!(sheet["password"]["hash"].as_str().unwrap().len(), 4);
+        assert!(sheet["password"].get("algorithm").is_none());
+        assert_eq!(sheet["format_cells"], true);
+        assert_eq!(sheet["insert_rows"], false);
+        assert_eq!(sheet["select_locked_cells"], true);
+
+        for format in ["json", "sql"] {
+            let body = multipart_body(&[
+                ("format", None, format.as_bytes()),
+                ("include_password_hashes", None, b"true"),
+                ("file", Some("a.xlsx"), &xlsx),
+            ]);
+            let (_, document) = post_raw("/convert", body).await;
+            let body = multipart_body(&[
+                ("format", None, format.as_bytes()),
+                ("file", None, &document),
+            ]);
+            let (status, restored) = post_raw("/restore", body).await;
+            assert_eq!(status, StatusCode::OK);
+            let body = multipart_body(&[
+                ("format", None, b"json"),
+                ("include_password_hashes", None, b"true"),
+                ("file", Some("a.xlsx"), &restored),
+            ]);
+            let (_, restored) = post("/convert", body).await;
+            assert_eq!(restored["protection"], json["protection"], "{}", format);
+            assert_eq!(restored["sheets"][0]["protection"], *sheet, "{}", format);
+            assert_eq!(restored["cells"][1]["locked"], false, "{}", format);
+        }
+
+        // 構造・ウィンドウを保護せず、パスワードだけを設定したブック
+        let xlsx = patch_part(
+            &rust_xlsxwriter::Workbook::new().save_to_buffer().unwrap(),
+            "xl/workbook.xml",
+            "<bookViews",
+            r#"<workbookProtection workbookPassword="CBEB"/><bookViews"#,
+        );
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            json["protection"],
+            serde_json::json!({ "structure": false, "windows": false, "has_password": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn round_trips_data_validations() {
+        use rust_xlsxwriter::{DataValidation, DataValidationRule};
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        let rule = DataValidation::new()
+            .allow_whole_number(DataValidationRule::Between(1, 10))
+            .set_multi_range("A1:A3 C1:C3");
+        ws.add_data_validation(0, 0, 2, 0, &rule).unwrap();
+        let lists = wb.add_worksheet().set_name("Lists").unwrap();
+        lists.write_column(0, 0, ["a", "b", "c"]).unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+        // 別シートを参照するリストは x14 の拡張領域に書かれる
+        let xlsx = patch_part(
+            &xlsx,
+            "xl/worksheets/sheet1.xml",
+            "</worksheet>",
+            r#"<extLst><ext uri="{CCE6A557-97BC-4b89-ADB6-D9C93CAAB3DF}" xmlns:x14="http://schemas.microsoft.com/office/spreadsheetml/2009/9/main"><x14:dataValidations count="1" xmlns:xm="http://schemas.microsoft.com/office/excel/2006/main"><x14:dataValidation type="list" allowBlank="1" showErrorMessage="1"><x14:formula1><xm:f>Lists!$A$1:$A$3</xm:f></x14:formula1><xm:sqref>E1:E5</xm:sqref></x14:dataValidation></x14:dataValidations></ext></extLst></worksheet>"#,
+        );
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let validations = &json["data_validations"];
+        assert_eq!(validations.as_array().unwrap().len(), 2);
+
+        let body = multipart_body(&[("format", None, b"sql"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, document) = post_raw("/convert", body).await;
+        let restored = restore_and_convert(&document, "sql").await;
+        assert_eq!(restored["data_validations"], *validations);
+    }
+
+    #[tokio::test]
+    async fn round_trips_conditional_formats() {
+        use rust_xlsxwriter::{
+            Color, ConditionalFormatCell, ConditionalFormatCellRule, ConditionalFormatIconSet,
+            ConditionalFormatIconType, Format,
+        };
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        let red = Format::new().set_background_color(Color::RGB(0xFFC7CE));
+        let bold = Format::new()
+            .set_bold()
+            .set_font_color(Color::RGB(0x006100));
+        ws.add_conditional_format(
+            0,
+            0,
+            9,
+            0,
+            &ConditionalFormatCell::new()
+                .set_rule(ConditionalFormatCellRule::GreaterThan(50))
+                .set_format(&red),
+        )
+        .unwrap();
+        ws.add_conditional_format(
+            0,
+            0,
+            9,
+            0,
+            &ConditionalFormatCell::new()
+                .set_rule(ConditionalFormatCellRule::LessThan(10))
+                .set_format(&bold),
+        )
+        .unwrap();
+        ws.add_conditional_format(
+            0,
+            1,
+            9,
+            1,
+            &ConditionalFormatIconSet::new()
+                .set_icon_type(ConditionalFormatIconType::ThreeArrows)
+                .reverse_icons(true)
+                .show_icons_only(true),
+        )
+        .unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        // 規則ごとの差分書式を id で引き、id 以外を比べられる形にする
+        let resolve = |json: &serde_json::Value| -> Vec<serde_json::Value> {
+            json["conditional_formats"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|rule| {
+                    let mut rule = rule.clone();
+                    if let Some(id) = rule.as_object_mut().unwrap().remove("style_id") {
+                        let mut style = json["differential_styles"]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .find(|s| s["id"] == id)
+                            .unwrap()
+                            .clone();
+                        style.as_object_mut().unwrap().remove("id");
+                        rule["style"] = style;
+                    }
+                    rule
+                })
+                .collect()
+        };
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let rules = resolve(&json);
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0]["style"]["fill"]["bg_color"], "#FFC7CE");
+        assert_eq!(rules[1]["style"]["font"]["bold"], true);
+        assert_eq!(rules[1]["style"]["font"]["color"], "#006100");
+
+        // 逆変換では差分書式を作り直すので、id ではなく中身で比べる
+        let body = multipart_body(&[("format", None, b"sql"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, document) = post_raw("/convert", body).await;
+        let restored = restore_and_convert(&document, "sql").await;
+        assert_eq!(resolve(&restored), rules);
+    }
+
+    #[tokio::test]
+    async fn round_trips_outlines() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        ws.set_row_height(0, 30).unwrap();
+        ws.group_rows(1, 3).unwrap();
+        ws.group_rows(2, 3).unwrap();
+        ws.group_rows_collapsed(6, 8).unwrap();
+        ws.group_columns_collapsed(1, 2).unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let sheet = &json["sheets"][0];
+        assert_eq!(sheet["rows"].as_array().unwrap().len(), 8);
+        assert_eq!(sheet["columns"].as_array().unwrap().len(), 2);
+
+        let body = multipart_body(&[("format", None, b"sql"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, document) = post_raw("/convert", body).await;
+        let restored = restore_and_convert(&document, "sql").await;
+        assert_eq!(restored["sheets"][0]["rows"], sheet["rows"]);
+        assert_eq!(restored["sheets"][0]["columns"], sheet["columns"]);
+    }
+
+    #[tokio::test]
+    async fn round_trips_print_titles() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        // 引用符で囲まれたシート名の中の ',' と '!' で範囲を区切らない
+        let ws = wb.add_worksheet().set_name("Q1, Sales!").unwrap();
+        ws.set_repeat_rows(0, 1).unwrap();
+        ws.set_repeat_columns(0, 1).unwrap();
+        ws.set_print_area(0, 0, 39, 5).unwrap();
+        wb.add_worksheet()
+            .set_name("Rows")
+            .unwrap()
+            .set_repeat_rows(2, 2)
+            .unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let titles = json["defined_names"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|n| n["name"] == "_xlnm.Print_Titles")
+            .unwrap();
+        assert_eq!(titles["refers_to"], "'Q1, Sales!'!$A:$B,'Q1, Sales!'!$1:$2");
+        assert_eq!(json["sheets"][0]["print_setup"]["repeat_cols"], "A:B");
+        assert_eq!(json["sheets"][1]["print_setup"]["repeat_rows"], "3:3");
+
+        let body = multipart_body(&[("format", None, b"sql"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, document) = post_raw("/convert", body).await;
+        let restored = restore_and_convert(&document, "sql").await;
+        for idx in 0..2 {
+            assert_eq!(
+                restored["sheets"][idx]["print_setup"],
+                json["sheets"][idx]["print_setup"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn emits_table_rows() {
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet();
+        ws.write_string(0, 0, "Name").unwrap();
+        ws.write_string(0, 1, "Score").unwrap();
+        ws.write_string(0, 2, "Double").unwrap();
+        ws.write_string(1, 0, "a").unwrap();
+        ws.write_number(1, 1, 10.0).unwrap();
+        ws.write_formula(1, 2, "=B2*2").unwrap();
+        ws.write_string(2, 0, "42").unwrap();
+        ws.write_string(4, 0, "outside").unwrap();
+        ws.add_table(
+            0,
+            0,
+            2,
+            2,
+            &rust_xlsxwriter::Table::new().set_name("Scores"),
+        )
+        .unwrap();
+        let xlsx = wb.save_to_buffer().unwrap();
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let (_, plain) = post("/convert", body).await;
+
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("table_mode", None, b"rows"),
+            ("value_mode", None, b"typed"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            json["tables"][0]["columns"],
+            serde_json::json!(["Name", "Score", "Double"])
+        );
+        assert_eq!(
+            json["table_rows"][0]["rows"],
+            serde_json::json!([
+                {
+                    "Name": { "type": "string", "value": "a" },
+                    "Score": { "type": "number", "value": 10 },
+                    "Double": { "type": "number", "value": 0 },
+                },
+                {
+                    "Name": { "type": "string", "value": "42" },
+                    "Score": null,
+                    "Double": null,
+                },
+            ])
+        );
+        // 行オブジェクトから作り直せないセル（数式）とテーブル外のセルだけが `cells` に残る
+        let addresses: Vec<_> = json["cells"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["address"].as_str().unwrap())
+            .collect();
+        assert_eq!(addresses, ["C2", "A5"]);
+        let restored = restore_and_convert(json.to_string().as_bytes(), "json").await;
+        assert_eq!(restored["tables"], plain["tables"]);
+        assert_eq!(restored["cells"], plain["cells"]);
+
+        // string モードでは数値に読める文字列（"42"）を `cells` に残して型を保つ
+        let body = multipart_body(&[
+            ("format", None, b"yaml"),
+            ("table_mode", None, b"rows"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (status, document) = post_raw("/convert", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let yaml: serde_json::Value = serde_yaml::from_slice(&document).unwrap();
+        assert_eq!(yaml["cells"].as_array().unwrap().len(), 3);
+        let restored = restore_and_convert(&document, "yaml").await;
+        assert_eq!(restored["cells"], plain["cells"]);
+
+        let body = multipart_body(&[
+            ("format", None, b"xml"),
+            ("table_mode", None, b"rows"),
+            ("file", Some("a.xlsx"), &xlsx),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "InvalidField");
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_format() {
+        let body = multipart_body(&[("file", Some("a.csv"), b"a,b\n")]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "MissingField");
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_or_empty_file() {
+        for path in ["/convert", "/restore"] {
+            let body = multipart_body(&[("format", None, b"json")]);
+            let (status, json) = post(path, body).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", path);
+            assert_eq!(json["error"], "MissingField", "{}", path);
+            assert_eq!(json["message"], "Missing 'file'", "{}", path);
+        }
+
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.csv"), b"")]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "ParseError");
+        assert_eq!(json["message"], "Invalid input document: file is empty");
+    }
+
+    #[tokio::test]
+    async fn rejects_non_utf8_text_fields() {
+        for path in ["/convert", "/restore"] {
+            let body = multipart_body(&[("format", None, b"js\xffon"), ("file", None, b"a\n")]);
+            let (status, json) = post(path, body).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", path);
+            assert_eq!(json["error"], "InvalidField");
+        }
+
+        let body = multipart_body(&[
+            ("format", None, b"json"),
+            ("input_format", None, b"\xc3\x28"),
+            ("file", None, b"a\n"),
+        ]);
+        let (status, json) = post("/convert", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], "InvalidField");
+    }
+
+    #[tokio::test]
+    async fn truncated_uploads_are_client_errors() {
+        let xlsx = sample_xlsx();
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+
+        for len in (0..body.len() - 4).step_by(37) {
+            let (status, json) = post("/convert", body[..len].to_vec()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "truncated at {}", len);
+            assert!(json["error"].is_string(), "truncated at {}", len);
+        }
+    }
+
+    #[tokio::test]
+    async fn mutated_uploads_never_fail_with_server_errors() {
+        let xlsx = sample_xlsx();
+        let body = multipart_body(&[("format", None, b"json"), ("file", Some("a.xlsx"), &xlsx)]);
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..200 {
+            let mut mutated = body.clone();
+            for _ in 0..(rng.next() % 8 + 1) {
+                let idx = (rng.next() % mutated.len() as u64) as usize;
+                mutated[idx] = rng.next() as u8;
+            }
+            let (status, json) = post("/convert", mutated).await;
+            assert!(
+                status.is_success() || status.is_client_error(),
+                "status {} body {}",
+                status,
+                json
+            );
+        }
+    }
+
+    /// 表示形式・シート名・注釈などに非 ASCII を含むブック
+    fn non_ascii_xlsx() -> Vec<u8> {
+        use rust_xlsxwriter::{DataValidation, Format, Note, Url};
+        let mut wb = rust_xlsxwriter::Workbook::new();
+        let ws = wb.add_worksheet().set_name("売上").unwrap();
+        for (row, code) in [
+            "#,##0 €",
+            "yyyy年m月d日",
+            "[$¥-411]#,##0;[赤]-#,##0",
+            "0.0\"ｇ\"",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            ws.write_number_with_format(
+                row as u32,
+                0,
+                45000.5,
+                &Format::new().set_num_format(code),
+            )
+            .unwrap();
+        }
+        let bold = Format::new().set_bold();
+        ws.write_rich_string(0, 1, &[(&bold, "東京"), (&Format::new(), "都")])
+            .unwrap();
+        ws.insert_note(0, 1, &Note::new("確認済み").set_author("山田"))
+            .unwrap();
+        ws.write_url(1, 1, Url::new("https://例え.jp/パス").set_text("リンク"))
+            .unwrap();
+        ws.add_data_validation(
+            2,
+            1,
+            3,
+            1,
+            &DataValidation::new()
+                .allow_list_strings(&["東京", "大阪"])
+                .unwrap(),
+        )
+        .unwrap();
+        // 描画のアンカーとウィンドウ枠の分割位置も数値の書き換え先にする
+        let mut chart = rust_xlsxwriter::Chart::new(rust_xlsxwriter::ChartType::Column);
+        chart.add_series().set_values("=売上!$A$1:$A$4");
+        ws.insert_chart(5, 3, &chart).unwrap();
+        ws.set_freeze_panes(1, 1).unwrap();
+        wb.define_name("範囲", "=売上!$A$1:$A$4").unwrap();
+        wb.save_to_buffer().unwrap()
+    }
+
+    #[tokio::test]
+    async fn mutated_parts_never_fail_with_server_errors() {
+        use std::io::{Read, Write};
+        let xlsx = non_ascii_xlsx();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(&xlsx)).unwrap();
+        let parts: Vec<(String, Vec<u8>)> = (0..archive.len())
+            .map(|i| {
+                let mut file = archive.by_index(i).unwrap();
+                let mut data = Vec::new();
+                file.read_to_end(&mut data).unwrap();
+                (file.name().to_string(), data)
+            })
+            .filter(|(name, _)| name.ends_with(".xml") || name.ends_with(".rels"))
+            .collect();
+        // 書式記号や多バイト文字を差し込み、ZIP としては正しいまま XML を壊す
+        let inserts: [&[u8]; 8] = [
+            "€".as_bytes(),
+            "年".as_bytes(),
+            b"\"",
+            b"\\",
+            b";",
+            b"[",
+            b"]",
+            &[0xE6],
+        ];
+        // 属性値・要素の本文の数値を、負の値や範囲外の値に置き換える
+        let numerals = ["-1", "4294967295", "1e300"];
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+
+        let mut mutations: Vec<(usize, Vec<u8>)> = Vec::new();
+        for _ in 0..200 {
+            let target = (rng.next() % parts.len() as u64) as usize;
+            let mut data = parts[target].1.clone();
+            for _ in 0..(rng.next() % 4 + 1) {
+                let idx = (rng.next() % data.len() as u64) as usize;
+                let insert = inserts[(rng.next() % inserts.len() as u64) as usize];
+                data.splice(idx..idx, insert.iter().copied());
+            }
+            mutations.push((target, data));
+        }
+        for (target, (_, data)) in parts.iter().enumerate() {
+            for (start, end) in numeric_values(data) {
+                for numeral in numerals {
+                    let mut data = data.clone();
+                    data.splice(start..end, numeral.bytes());
+                    mutations.push((target, data));
+                }
+            }
+        }
+
+        for (target, data) in mutations {
+            let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            for (i, (name, original)) in parts.iter().enumerate() {
+                writer.start_file(name.as_str(), options).unwrap();
+                writer
+                    .write_all(if i == target { &data } else { original })
+                    .unwrap();
+            }
+            let mutated = writer.finish().unwrap().into_inner();
+
+            let body = multipart_body(&[
+                ("format", None, b"json"),
+                ("value_mode", None, b"typed"),
+                ("include_styles", None, b"true"),
+                ("file", Some("a.xlsx"), &mutated),
+            ]);
+            let (status, json) = post("/convert", body).await;
+            assert!(
+                status.is_success() || status.is_client_error(),
+                "{}: status {} body {}",
+                parts[target].0,
+                status,
+                json
+            );
+        }
+    }
+
+    /// `"12"` や `>12<` のように、属性値・本文全体が数値になっている位置
+    fn numeric_values(xml: &[u8]) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        let mut i = 1;
+        while i < xml.len() {
+            if xml[i].is_ascii_digit() && matches!(xml[i - 1], b'"' | b'>') {
+                let end = i + xml[i..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit() || **b == b'.')
+                    .count();
+                if matches!(xml.get(end), Some(b'"' | b'<')) {
+                    found.push((i, end));
+                }
+                i = end;
+            } else {
+                i += 1;
+            }
+        }
+        found
+    }
+
+    #[tokio::test]
+    async fn random_file_contents_are_rejected() {
+        let mut rng = XorShift(42);
+        for len in [0usize, 1, 8, 64, 4096] {
+            let data: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+            for path in ["/convert", "/restore"] {
+                let body = multipart_body(&[("format", None, b"json"), ("file", None, &data)]);
+                let (status, _) = post(path, body).await;
+                assert!(!status.is_server_error(), "{} len {}", path, len);
+            }
+        }
+    }
 }
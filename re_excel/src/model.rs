@@ -0,0 +1,1029 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename = "workbook")]
+pub struct Workbook {
+    /// 入力ファイルから判別したフォーマット（逆変換の入力では省略可）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_format: Option<SourceFormat>,
+    pub sheets: Vec<SheetMetadata>,
+    #[serde(default)]
+    pub cells: Vec<CellData>,
+    #[serde(default)]
+    pub merged_ranges: Vec<MergedRange>,
+    /// 1904 年基準の日付システムか（`serial` の解釈に使う）。
+    /// ブックのフラグを読めない入力（.ods / .csv、日付セルの無い .xls / .xlsb）では省略する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date1904: Option<bool>,
+    /// 重複を除いたセル書式のテーブル（`include_styles` 指定時のみ）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub styles: Vec<CellStyle>,
+    /// セルのコメント（メモ）とスレッド形式のコメント
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<Comment>,
+    /// セル（範囲）に設定されたハイパーリンク
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hyperlinks: Vec<Hyperlink>,
+    /// 名前付き範囲・名前付き数式
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub defined_names: Vec<DefinedName>,
+    /// テーブル（ListObject）の定義
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<Table>,
+    /// 入力規則（ドロップダウンリストなど）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data_validations: Vec<DataValidation>,
+    /// 条件付き書式
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditional_formats: Vec<ConditionalFormat>,
+    /// 条件付き書式から参照される差分書式（`id` は `dxfs` 内の位置）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub differential_styles: Vec<CellStyle>,
+    /// シート上の画像・図形・グラフ
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drawings: Vec<Drawing>,
+    /// グラフの定義（種類・タイトル・系列の参照範囲）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub charts: Vec<Chart>,
+    /// ピボットテーブルの定義（逆変換では作り直さず、出力範囲は値として書き込む）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pivot_tables: Vec<PivotTable>,
+    /// ブックの保護（保護されていなければ無し）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protection: Option<WorkbookProtection>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceFormat {
+    Xlsx,
+    Xls,
+    Xlsb,
+    Ods,
+    Csv,
+}
+
+impl SourceFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceFormat::Xlsx => "xlsx",
+            SourceFormat::Xls => "xls",
+            SourceFormat::Xlsb => "xlsb",
+            SourceFormat::Ods => "ods",
+            SourceFormat::Csv => "csv",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "xlsx" => Some(SourceFormat::Xlsx),
+            "xls" => Some(SourceFormat::Xls),
+            "xlsb" => Some(SourceFormat::Xlsb),
+            "ods" => Some(SourceFormat::Ods),
+            "csv" => Some(SourceFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct SheetMetadata {
+    pub name: String,
+    pub index: usize,
+    /// 後方互換のためのフラグ。`visibility` が visible 以外なら true
+    pub hidden: bool,
+    #[serde(default)]
+    pub visibility: SheetVisibility,
+    #[serde(default)]
+    pub sheet_type: SheetKind,
+    /// シート見出しの色
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab_color: Option<String>,
+    /// 表示倍率（%）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<u16>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub hide_gridlines: bool,
+    /// 既定の行の高さ（ポイント）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_row_height: Option<f64>,
+    /// 既定の列幅（OOXML の文字幅単位）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_col_width: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freeze_panes: Option<FreezePanes>,
+    /// 幅・非表示・アウトラインが既定と異なる列
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<ColumnLayout>,
+    /// 高さ・非表示・アウトラインが既定と異なる行
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rows: Vec<RowLayout>,
+    /// 印刷設定（すべて既定値なら無し）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub print_setup: Option<PrintSetup>,
+    /// シートの保護（保護されていなければ無し）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protection: Option<SheetProtection>,
+}
+
+/// ブックの保護（`workbookProtection`）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct WorkbookProtection {
+    /// シートの追加・削除・移動・名前変更などを禁止する
+    #[serde(default)]
+    pub structure: bool,
+    /// ウィンドウの位置・大きさを固定する
+    #[serde(default)]
+    pub windows: bool,
+    /// パスワードが設定されている。ハッシュが無ければ逆変換ではパスワード無しの保護になり、
+    /// その件数（シートの保護を含む）を `X-Dropped-Password-Protection` で返す
+    #[serde(default)]
+    pub has_password: bool,
+    /// パスワードのハッシュ（`include_password_hashes` 指定時のみ）。逆変換ではこれを書き戻す
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<PasswordHash>,
+}
+
+/// シートの保護（`sheetProtection`）。各フラグは保護中でもユーザーに許可される操作
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SheetProtection {
+    /// パスワードが設定されている
+    #[serde(default)]
+    pub has_password: bool,
+    /// パスワードのハッシュ（`include_password_hashes` 指定時のみ）。逆変換ではこれを書き戻す
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<PasswordHash>,
+    #[serde(default = "default_true")]
+    pub select_locked_cells: bool,
+    #[serde(default = "default_true")]
+    pub select_unlocked_cells: bool,
+    #[serde(default)]
+    pub format_cells: bool,
+    #[serde(default)]
+    pub format_columns: bool,
+    #[serde(default)]
+    pub format_rows: bool,
+    #[serde(default)]
+    pub insert_columns: bool,
+    #[serde(default)]
+    pub insert_rows: bool,
+    #[serde(default)]
+    pub insert_hyperlinks: bool,
+    #[serde(default)]
+    pub delete_columns: bool,
+    #[serde(default)]
+    pub delete_rows: bool,
+    #[serde(default)]
+    pub sort: bool,
+    #[serde(default)]
+    pub auto_filter: bool,
+    #[serde(default)]
+    pub pivot_tables: bool,
+    /// 図形・コメントの編集
+    #[serde(default)]
+    pub edit_objects: bool,
+    #[serde(default)]
+    pub edit_scenarios: bool,
+}
+
+/// 保護のパスワードのハッシュ（パスワードそのものは含まれない）。
+/// `algorithm` が無ければ旧形式の 16 ビットハッシュで、`hash` は 16 進 4 桁
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PasswordHash {
+    /// `SHA-512` など
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    /// ハッシュ値（旧形式以外は base64）
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spin_count: Option<u32>,
+}
+
+/// 印刷範囲・印刷タイトル・用紙・余白・ヘッダー/フッター・拡大縮小・改ページ
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct PrintSetup {
+    /// 印刷範囲（例: "A1:F40"）。複数範囲は空白区切り
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub print_area: Option<String>,
+    /// 各ページに繰り返す行（例: "1:2"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_rows: Option<String>,
+    /// 各ページに繰り返す列（例: "A:B"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_cols: Option<String>,
+    /// `landscape` のみ（縦向きは既定）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<String>,
+    /// OOXML の用紙サイズ番号（9 = A4 など）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paper_size: Option<u32>,
+    /// 拡大縮小率（%）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<u32>,
+    /// 「次のページ数に合わせて印刷」の横・縦のページ数（0 は自動）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit_to_width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit_to_height: Option<u32>,
+    /// 既定（左右 0.7、上下 0.75、ヘッダー/フッター 0.3 インチ）と異なる場合のみ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margins: Option<PageMargins>,
+    /// `&P` などの制御コードを含むヘッダー文字列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer: Option<String>,
+    /// この行（1 始まり）の後で改ページする
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub row_breaks: Vec<u32>,
+    /// この列（1 始まり）の後で改ページする
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub col_breaks: Vec<u32>,
+}
+
+/// 余白（インチ）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PageMargins {
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+    pub header: f64,
+    pub footer: f64,
+}
+
+impl Default for PageMargins {
+    fn default() -> Self {
+        PageMargins {
+            left: 0.7,
+            right: 0.7,
+            top: 0.75,
+            bottom: 0.75,
+            header: 0.3,
+            footer: 0.3,
+        }
+    }
+}
+
+/// ウィンドウ枠の固定。`rows` 行・`cols` 列が固定される
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct FreezePanes {
+    pub rows: u32,
+    pub cols: u32,
+    /// スクロール後の左上セル（固定位置の直後のセルと異なる場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_left_cell: Option<String>,
+}
+
+/// 連続する列の設定（列番号は 1 始まり）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ColumnLayout {
+    pub first_col: u32,
+    pub last_col: u32,
+    /// OOXML の文字幅単位（余白を含む）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub hidden: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub outline_level: u8,
+    /// 直前のグループが折りたたまれている
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub collapsed: bool,
+}
+
+/// 行の設定（行番号は 1 始まり）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct RowLayout {
+    pub row: u32,
+    /// 手動で設定された高さ（ポイント）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub hidden: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub outline_level: u8,
+    /// 直前のグループが折りたたまれている
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub collapsed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetVisibility {
+    #[default]
+    Visible,
+    Hidden,
+    /// UI から再表示できない（VBA でのみ解除可能）
+    VeryHidden,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetKind {
+    #[default]
+    Worksheet,
+    ChartSheet,
+    MacroSheet,
+    DialogSheet,
+    Vba,
+}
+
+impl SheetVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SheetVisibility::Visible => "visible",
+            SheetVisibility::Hidden => "hidden",
+            SheetVisibility::VeryHidden => "very_hidden",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "visible" => Some(SheetVisibility::Visible),
+            "hidden" => Some(SheetVisibility::Hidden),
+            "very_hidden" => Some(SheetVisibility::VeryHidden),
+            _ => None,
+        }
+    }
+}
+
+impl SheetKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SheetKind::Worksheet => "worksheet",
+            SheetKind::ChartSheet => "chart_sheet",
+            SheetKind::MacroSheet => "macro_sheet",
+            SheetKind::DialogSheet => "dialog_sheet",
+            SheetKind::Vba => "vba",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "worksheet" => Some(SheetKind::Worksheet),
+            "chart_sheet" => Some(SheetKind::ChartSheet),
+            "macro_sheet" => Some(SheetKind::MacroSheet),
+            "dialog_sheet" => Some(SheetKind::DialogSheet),
+            "vba" => Some(SheetKind::Vba),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CellData {
+    pub sheet: String,
+    pub address: String,
+    pub row: u32,
+    pub col: u32,
+    pub data_type: CellType,
+    #[serde(deserialize_with = "plain_or_typed_value")]
+    pub value: String,
+    /// 数式（先頭の `=` を除く）。数式の無いセルでも `null` として出力する
+    #[serde(default)]
+    pub formula: Option<String>,
+    /// 日付・時刻・期間セルの元のシリアル値（`value` は ISO 8601 表記）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial: Option<f64>,
+    /// 表示形式コード（「標準」以外のときのみ。例: `0.0%`, `"¥"#,##0`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number_format: Option<String>,
+    /// 表示形式を適用した、Excel 上で見える文字列（.xlsx のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_text: Option<String>,
+    /// `Workbook::styles` の `id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style_id: Option<usize>,
+    /// 書式付き文字列の区間（連結すると `value` になる。.xlsx のみ）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runs: Vec<TextRun>,
+    /// ふりがな（.xlsx のみ）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phonetic: Vec<PhoneticRun>,
+    /// ピボットテーブルの出力範囲内のセルなら、そのピボットテーブルの名前。
+    /// 値は集計元から派生したもので、元データと二重に数えないよう区別する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pivot_table: Option<String>,
+    /// セルのロック。シートが保護されていると、ロックされていないセルだけを編集できる（.xlsx のみ）
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub locked: bool,
+}
+
+/// `value` は XML では `<value>text</value>`（`#text`）または typed モードの
+/// `<value><type>number</type><value>1.5</value></value>` として届く。
+/// serde-xml-rs は `deserialize_any` に対応しないため構造体として読み、JSON / YAML も
+/// 読み込み前に `{"value": "..."}` の形へ揃えている（`restore::from_value`）
+fn plain_or_typed_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    struct ValueVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for ValueVisitor {
+        type Value = String;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a cell value")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<String, A::Error> {
+            let mut value = String::new();
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "#text" | "value" => value = map.next_value()?,
+                    _ => {
+                        map.next_value::<serde::de::IgnoredAny>()?;
+                    }
+                }
+            }
+            Ok(value)
+        }
+    }
+
+    deserializer.deserialize_struct("value", &["#text", "type", "value"], ValueVisitor)
+}
+
+/// 書式付き文字列の 1 区間。`font` は区間に指定されたフォント（無ければセルの書式に従う）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TextRun {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<FontStyle>,
+}
+
+/// ふりがな。`start`〜`end` は読みを振る本文の文字位置（0 始まり、`end` は含まない）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PhoneticRun {
+    pub text: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// XML でも `<data_type>Number</data_type>` のようにテキストで出力するため手動で直列化する
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CellType {
+    String,
+    Number,
+    Boolean,
+    Error,
+    /// 日付・時刻・日時（ISO 8601）
+    DateTime,
+    /// `[h]:mm:ss` などの期間書式（ISO 8601 の期間表記）
+    Duration,
+    DateTimeIso,
+    DurationIso,
+    /// 値のないセル（キャッシュ値のない数式セル、書式だけが設定されたセル）
+    Empty,
+}
+
+impl CellType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CellType::String => "String",
+            CellType::Number => "Number",
+            CellType::Boolean => "Boolean",
+            CellType::Error => "Error",
+            CellType::DateTime => "DateTime",
+            CellType::Duration => "Duration",
+            CellType::DateTimeIso => "DateTimeIso",
+            CellType::DurationIso => "DurationIso",
+            CellType::Empty => "Empty",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "String" => Some(CellType::String),
+            "Number" => Some(CellType::Number),
+            "Boolean" => Some(CellType::Boolean),
+            "Error" => Some(CellType::Error),
+            "DateTime" => Some(CellType::DateTime),
+            "Duration" => Some(CellType::Duration),
+            "DateTimeIso" => Some(CellType::DateTimeIso),
+            "DurationIso" => Some(CellType::DurationIso),
+            "Empty" => Some(CellType::Empty),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for CellType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for CellType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        CellType::from_name(&s)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown data_type: {}", s)))
+    }
+}
+
+/// `parse_excel` が `{:?}` で出力したエラー種別（または Excel 表記）を Excel のエラー値に揃える
+pub fn error_literal(value: &str) -> Option<&'static str> {
+    match value {
+        "Div0" | "#DIV/0!" => Some("#DIV/0!"),
+        "NA" | "#N/A" => Some("#N/A"),
+        "Name" | "#NAME?" => Some("#NAME?"),
+        "Null" | "#NULL!" => Some("#NULL!"),
+        "Num" | "#NUM!" => Some("#NUM!"),
+        "Ref" | "#REF!" => Some("#REF!"),
+        "Value" | "#VALUE!" => Some("#VALUE!"),
+        _ => None,
+    }
+}
+
+/// セル書式。名前（`solid`, `thin`, `center` など）は OOXML の属性値、色は `#RRGGBB`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct CellStyle {
+    pub id: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<FontStyle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill: Option<FillStyle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border: Option<BorderStyle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<AlignmentStyle>,
+    /// 既定（ロック有り・数式表示）と異なる場合のみ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protection: Option<ProtectionStyle>,
+    /// 差分書式の表示形式。セル書式の表示形式はセル側（`number_format`）に持つ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number_format: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct FontStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub bold: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub italic: bool,
+    /// `single` / `double` / `singleAccounting` / `doubleAccounting`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underline: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub strike: bool,
+    /// `superscript` / `subscript`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct FillStyle {
+    pub pattern: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fg_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bg_color: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct BorderStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left: Option<BorderEdge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right: Option<BorderEdge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top: Option<BorderEdge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom: Option<BorderEdge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagonal: Option<BorderEdge>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub diagonal_up: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub diagonal_down: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct BorderEdge {
+    pub style: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct AlignmentStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub horizontal: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertical: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub wrap_text: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub shrink_to_fit: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indent: Option<u32>,
+    /// 0〜180 の角度、255 は縦書き
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_rotation: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ProtectionStyle {
+    #[serde(default = "default_true")]
+    pub locked: bool,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+pub(crate) fn is_true(b: &bool) -> bool {
+    *b
+}
+
+fn is_zero<T: Default + PartialEq>(n: &T) -> bool {
+    *n == T::default()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MergedRange {
+    pub sheet: String,
+    pub start: String,
+    pub end: String,
+}
+
+/// セルに付いたコメント。スレッド形式の場合は返信を `replies` に持つ。
+/// 逆変換では先頭のコメントだけをメモとして書き、返信の件数を `X-Dropped-Comment-Replies` で返す
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Comment {
+    pub sheet: String,
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<CommentReply>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CommentReply {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub text: String,
+}
+
+/// `target` は外部リンクなら URL（`#` 以降にブック内の位置を含むことがある）、
+/// ブック内リンクなら "Sheet2!A1" のような参照先
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Hyperlink {
+    pub sheet: String,
+    /// "A1" または "A1:B2"
+    pub range: String,
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+    #[serde(default)]
+    pub internal: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DefinedName {
+    pub name: String,
+    /// シートスコープの名前ならそのシート名（ブック全体なら省略）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// 参照先の式（例: "Sheet1!$A$1:$B$10"）。先頭の `=` は含めない
+    pub refers_to: String,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// Excel のテーブル。`range` は見出し行・集計行を含む全体の範囲
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Table {
+    pub name: String,
+    pub sheet: String,
+    pub range: String,
+    #[serde(default = "default_true")]
+    pub header_row: bool,
+    #[serde(default)]
+    pub totals_row: bool,
+    /// 列見出し（左から順に）
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// テーブルスタイル名（例: "TableStyleMedium2"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+}
+
+/// ピボットテーブル。フィールドは集計元（ピボットキャッシュ）の列見出しで表す
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PivotTable {
+    pub name: String,
+    pub sheet: String,
+    /// 出力範囲（レポートフィルターの行は含まない）
+    pub range: String,
+    /// 集計元の種類（`worksheet` / `external` / `consolidation` / `scenario`）
+    pub source_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_sheet: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_range: Option<String>,
+    /// 集計元が名前付き範囲・テーブルの場合の名前
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_name: Option<String>,
+    /// 行・列のフィールド。複数の値フィールドを並べる位置は「値」の見出し（既定は "Values"）で表す
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub row_fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub column_fields: Vec<String>,
+    /// レポートフィルターのフィールド
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub page_fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data_fields: Vec<PivotDataField>,
+}
+
+/// ピボットテーブルの値フィールド
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PivotDataField {
+    /// 表示名（例: "Sum of Amount"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub field: String,
+    /// 集計方法（`sum`, `count`, `average`, `max`, `min`, `product`, `countNums`, `stdDev`, `stdDevp`, `var`, `varp`）
+    pub function: String,
+}
+
+/// 入力規則。種類・演算子は OOXML の名前（`list`, `whole`, `between` など）のまま保持する
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DataValidation {
+    pub sheet: String,
+    /// 対象範囲。複数範囲は空白区切り（例: "A1:A10 C1:C10"）
+    pub range: String,
+    pub validation_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    /// リストの値（`"a,b,c"` 形式）や範囲参照、下限値など
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formula1: Option<String>,
+    /// `between` / `notBetween` の上限値
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formula2: Option<String>,
+    #[serde(default)]
+    pub allow_blank: bool,
+    /// リストのドロップダウンを表示するか（OOXML の `showDropDown` とは真偽が逆）
+    #[serde(default = "default_true")]
+    pub show_dropdown: bool,
+    #[serde(default)]
+    pub show_input_message: bool,
+    #[serde(default)]
+    pub show_error_message: bool,
+    /// `stop`（既定）/ `warning` / `information`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_message: Option<String>,
+}
+
+/// 条件付き書式の 1 ルール。種類・演算子は OOXML の名前（`cellIs`, `iconSet` など）のまま保持する
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ConditionalFormat {
+    pub sheet: String,
+    /// 対象範囲。複数範囲は空白区切り
+    pub range: String,
+    pub rule_type: String,
+    /// シート内での評価順（小さいほど優先）
+    pub priority: u32,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub stop_if_true: bool,
+    /// 適用する差分書式（`differential_styles` の `id`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style_id: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub formulas: Vec<String>,
+    /// `containsText` などで探す文字列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// `timePeriod` の期間（`today`, `last7Days` など）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_period: Option<String>,
+    /// `top10` の件数（`percent` なら割合）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<u32>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub percent: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub bottom: bool,
+    /// `aboveAverage` で平均未満を対象にする
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub below_average: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub equal_average: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub std_dev: Option<u32>,
+    /// アイコンセットの名前（`3TrafficLights1` など）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_set: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub reverse: bool,
+    /// アイコン・データバーのみを表示し、値を隠す
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub hide_value: bool,
+    /// カラースケール・データバー・アイコンセットの閾値
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thresholds: Vec<ConditionalThreshold>,
+    /// カラースケールの各閾値の色、またはデータバーの色
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub colors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ConditionalThreshold {
+    /// `min` / `max` / `num` / `percent` / `percentile` / `formula` など
+    pub value_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// 「以上」ではなく「より大きい」で判定する（アイコンセット）
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub greater_than: bool,
+}
+
+/// シート上の描画オブジェクト。グループ化された図形はグループの配置で個別に出力する
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Drawing {
+    pub sheet: String,
+    pub kind: DrawingKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 配置先のセル範囲（例: "B2:D8"）。セルに固定しない場合は省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+    /// 左上のセルからのずれ（ピクセル）
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub x_offset: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub y_offset: u32,
+    /// 表示サイズ（ピクセル）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// 代替テキスト
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<String>,
+    /// 図形の種類（`rect`, `roundRect` など OOXML のプリセット名）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shape_type: Option<String>,
+    /// 図形内の文字列（段落は改行区切り）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// 画像のパッケージ内パス（例: "xl/media/image1.png"）。ZIP 出力ではこの名前で同梱する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<String>,
+    /// 画像の MIME タイプ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    /// 画像の中身（base64。`include_media=inline` 指定時のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawingKind {
+    Image,
+    Shape,
+    Connector,
+    Chart,
+    /// SmartArt などグラフ以外のグラフィックフレーム
+    Graphic,
+}
+
+impl DrawingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DrawingKind::Image => "image",
+            DrawingKind::Shape => "shape",
+            DrawingKind::Connector => "connector",
+            DrawingKind::Chart => "chart",
+            DrawingKind::Graphic => "graphic",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "image" => Some(DrawingKind::Image),
+            "shape" => Some(DrawingKind::Shape),
+            "connector" => Some(DrawingKind::Connector),
+            "chart" => Some(DrawingKind::Chart),
+            "graphic" => Some(DrawingKind::Graphic),
+            _ => None,
+        }
+    }
+}
+
+/// グラフの定義。配置は対応する `Drawing` と同じ
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Chart {
+    pub sheet: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 配置先のセル範囲。チャートシートのグラフでは省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub x_offset: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub y_offset: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// 最初のプロットの種類（`bar`, `line`, `pie`, `scatter` など OOXML の `*Chart` 要素名から `Chart` を除いたもの）
+    pub chart_type: String,
+    /// 棒グラフの向き（`col` = 縦棒 / `bar` = 横棒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bar_direction: Option<String>,
+    /// `clustered` / `stacked` / `percentStacked` / `standard`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grouping: Option<String>,
+    /// 散布図・レーダーチャートの描き方（`scatterStyle` / `radarStyle` の値）。
+    /// 散布図で系列の線がすべて消されている場合は `marker`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub series: Vec<ChartSeries>,
+}
+
+/// グラフの系列。参照範囲は "Sheet1!$B$2:$B$13" のような式（先頭の `=` は含めない）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ChartSeries {
+    /// 系列名（参照の場合はキャッシュされた値）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_ref: Option<String>,
+    /// 項目（散布図では X 値）の範囲
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub categories: Option<String>,
+    /// 値（散布図では Y 値）の範囲
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<String>,
+    /// 複合グラフで、グラフ全体の種類と異なるプロットに属する場合のその種類
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chart_type: Option<String>,
+}
+
+/// "B2" のような A1 形式のアドレスを 0-based の (row, col) に変換する
+pub fn parse_address(address: &str) -> Option<(u32, u32)> {
+    let address = address.replace('$', "");
+    let split = address.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = address.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut col: u32 = 0;
+    for c in letters.chars() {
+        col = col
+            .checked_mul(26)?
+            .checked_add(c.to_ascii_uppercase() as u32 - 'A' as u32 + 1)?;
+    }
+    let row: u32 = digits.parse().ok()?;
+    if row == 0 {
+        return None;
+    }
+    Some((row - 1, col - 1))
+}
+
+pub fn col_to_letter(mut col: u32) -> String {
+    let mut s = String::new();
+    while col > 0 {
+        let rem = (col - 1) % 26;
+        s.insert(0, (b'A' + rem as u8) as char);
+        col = (col - 1) / 26;
+    }
+    s
+}
@@ -0,0 +1,738 @@
+use crate::model::{CellData, CellType, error_literal};
+use chrono::{Datelike, Duration, NaiveDate};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+/// Excel が日付・時刻として表示できる最大のシリアル値（9999-12-31 23:59:59.999）
+pub const MAX_DATE_SERIAL: f64 = 2_958_465.999_999;
+
+/// ロケールに依存しない組み込み表示形式（numFmtId → 書式コード）
+pub fn builtin(id: u32) -> Option<&'static str> {
+    Some(match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "m/d/yyyy",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "m/d/yyyy h:mm",
+        37 => "#,##0 ;(#,##0)",
+        38 => "#,##0 ;[Red](#,##0)",
+        39 => "#,##0.00;(#,##0.00)",
+        40 => "#,##0.00;[Red](#,##0.00)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mmss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    })
+}
+
+/// 書式コードに従って Excel がセルに表示する文字列を組み立てる（列幅や条件付き書式は考慮しない）
+pub fn display_text(cell: &CellData, code: &str, date1904: bool) -> String {
+    match cell.data_type {
+        CellType::Empty => String::new(),
+        CellType::Boolean => cell.value.to_uppercase(),
+        CellType::Error => error_literal(&cell.value)
+            .unwrap_or(&cell.value)
+            .to_string(),
+        CellType::Number | CellType::DateTime | CellType::Duration => {
+            let number = match cell.data_type {
+                CellType::Number => cell.value.parse::<f64>().ok(),
+                _ => cell.serial,
+            };
+            match number {
+                Some(n) => format_number(n, code, date1904),
+                None => cell.value.clone(),
+            }
+        }
+        CellType::String | CellType::DateTimeIso | CellType::DurationIso => {
+            format_text(&cell.value, code)
+        }
+    }
+}
+
+/// 「標準」書式。既定の列幅（11 文字）に収まる範囲で表示する
+pub fn general(n: f64) -> String {
+    if n == 0.0 || !n.is_finite() {
+        return if n.is_finite() {
+            "0".into()
+        } else {
+            n.to_string()
+        };
+    }
+    let exponent = n.abs().log10().floor() as i32;
+    if !(-5..11).contains(&exponent) {
+        let formatted = format!("{:.5e}", n);
+        let (mantissa, exp) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+        let mantissa = trim_fraction(mantissa);
+        let exp: i32 = exp.parse().unwrap_or(0);
+        return format!(
+            "{}E{}{:02}",
+            mantissa,
+            if exp < 0 { '-' } else { '+' },
+            exp.abs()
+        );
+    }
+    let decimals = (10 - exponent.max(0) - 1).max(0) as usize;
+    trim_fraction(&format!("{:.*}", decimals, n)).to_string()
+}
+
+fn trim_fraction(s: &str) -> &str {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        s
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Literal(String),
+    General,
+    /// 数値のプレースホルダ `0` / `#` / `?`
+    Digit(char),
+    Dot,
+    Comma,
+    Percent,
+    /// 指数表記 `E+` / `E-`
+    Exponent(char),
+    Text,
+    /// 日付・時刻の要素（`yyyy`, `mm`, `d`, `h`, `ss` など。小文字化済み）
+    Date(String),
+    /// 秒の小数部（桁数）
+    SubSecond(usize),
+    /// 経過時間 `[h]` / `[mm]` / `[ss]`（単位と桁数）
+    Elapsed(char, usize),
+    AmPm(String),
+    /// 分数（`# ?/?` など）。近似せず「標準」で表示する
+    Fraction,
+}
+
+/// `;` で区切られたセクションに分割する（引用符・角括弧・エスケープ内は区切らない）
+fn split_sections(code: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let (mut start, mut quoted, mut bracket, mut escaped) = (0, false, false, false);
+    for (i, c) in code.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if !quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '[' if !quoted => bracket = true,
+            ']' if !quoted => bracket = false,
+            ';' if !quoted && !bracket => {
+                sections.push(&code[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    sections.push(&code[start..]);
+    sections
+}
+
+fn tokenize(section: &str) -> Vec<Token> {
+    let chars: Vec<char> = section.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i..];
+        match c {
+            '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == '"')
+                    .map_or(chars.len(), |p| i + 1 + p);
+                tokens.push(Token::Literal(chars[i + 1..end].iter().collect()));
+                i = end + 1;
+            }
+            '\\' => {
+                if let Some(&next) = chars.get(i + 1) {
+                    tokens.push(Token::Literal(next.to_string()));
+                }
+                i += 2;
+            }
+            // `_x` は x の幅の空白、`*x` は列幅まで x を繰り返す（幅は考慮しない）
+            '_' => {
+                tokens.push(Token::Literal(" ".into()));
+                i += 2;
+            }
+            '*' => i += 2,
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map_or(chars.len(), |p| i + p);
+                let inner: String = chars[i + 1..end].iter().collect();
+                let lower = inner.to_lowercase();
+                if let Some(currency) = inner.strip_prefix('$') {
+                    let symbol = currency.split('-').next().unwrap_or_default();
+                    tokens.push(Token::Literal(symbol.to_string()));
+                } else if !lower.is_empty()
+                    && lower.chars().all(|c| c == lower.chars().next().unwrap())
+                    && matches!(lower.chars().next(), Some('h' | 'm' | 's'))
+                {
+                    tokens.push(Token::Elapsed(lower.chars().next().unwrap(), lower.len()));
+                }
+                // 色（[Red]）や条件（[>100]）は表示文字列に影響しないので読み飛ばす
+                i = end + 1;
+            }
+            '0' | '#' | '?' => {
+                tokens.push(Token::Digit(c));
+                i += 1;
+            }
+            '.' => {
+                let zeros = chars[i + 1..].iter().take_while(|&&c| c == '0').count();
+                let after_seconds = tokens.iter().rev().find_map(|t| match t {
+                    Token::Date(d) => Some(d.starts_with('s')),
+                    Token::Elapsed(e, _) => Some(*e == 's'),
+                    Token::Digit(_) => Some(false),
+                    _ => None,
+                });
+                if zeros > 0 && after_seconds == Some(true) {
+                    tokens.push(Token::SubSecond(zeros));
+                    i += 1 + zeros;
+                } else {
+                    tokens.push(Token::Dot);
+                    i += 1;
+                }
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '%' => {
+                tokens.push(Token::Percent);
+                i += 1;
+            }
+            'E' | 'e' if matches!(chars.get(i + 1), Some('+' | '-')) => {
+                tokens.push(Token::Exponent(chars[i + 1]));
+                i += 2;
+            }
+            '@' => {
+                tokens.push(Token::Text);
+                i += 1;
+            }
+            '/' if matches!(tokens.last(), Some(Token::Digit(_))) => {
+                tokens.push(Token::Fraction);
+                i += 1;
+            }
+            _ if starts_with_keyword(rest, "general") => {
+                tokens.push(Token::General);
+                i += 7;
+            }
+            _ if starts_with_keyword(rest, "am/pm") => {
+                tokens.push(Token::AmPm(rest[..5].iter().collect()));
+                i += 5;
+            }
+            _ if starts_with_keyword(rest, "a/p") => {
+                tokens.push(Token::AmPm(rest[..3].iter().collect()));
+                i += 3;
+            }
+            'y' | 'Y' | 'm' | 'M' | 'd' | 'D' | 'h' | 'H' | 's' | 'S' => {
+                let lower = c.to_ascii_lowercase();
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|c| c.to_ascii_lowercase() == lower)
+                    .count();
+                tokens.push(Token::Date(lower.to_string().repeat(len)));
+                i += len;
+            }
+            _ => {
+                tokens.push(Token::Literal(c.to_string()));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// 大文字小文字を区別せず `keyword`（ASCII）で始まるか
+fn starts_with_keyword(chars: &[char], keyword: &str) -> bool {
+    chars.len() >= keyword.len()
+        && chars
+            .iter()
+            .zip(keyword.chars())
+            .all(|(c, k)| c.eq_ignore_ascii_case(&k))
+}
+
+pub fn format_number(n: f64, code: &str, date1904: bool) -> String {
+    let sections = split_sections(code);
+    let (section, value, sign) = match sections.len() {
+        1 => (sections[0], n, n < 0.0),
+        _ if n > 0.0 => (sections[0], n, false),
+        _ if n < 0.0 => (sections[1], -n, false),
+        2 => (sections[0], n, false),
+        _ => (sections[2], n, false),
+    };
+    let tokens = tokenize(section);
+
+    if tokens.contains(&Token::Fraction) {
+        return general(n);
+    }
+    let is_date = tokens.iter().any(|t| {
+        matches!(
+            t,
+            Token::Date(_) | Token::Elapsed(..) | Token::AmPm(_) | Token::SubSecond(_)
+        )
+    });
+    let body = if is_date {
+        format_date(value, &tokens, date1904).unwrap_or_else(|| general(value))
+    } else {
+        format_digits(value.abs(), &tokens)
+    };
+    let negative = sign && !is_date && body.chars().any(|c| c.is_ascii_digit() && c != '0');
+    if negative { format!("-{}", body) } else { body }
+}
+
+fn format_text(text: &str, code: &str) -> String {
+    let sections = split_sections(code);
+    let section = match sections.len() {
+        4.. => sections[3],
+        1 if sections[0].contains('@') => sections[0],
+        _ => return text.to_string(),
+    };
+    tokenize(section)
+        .into_iter()
+        .filter_map(|t| match t {
+            Token::Literal(s) => Some(s),
+            Token::Text => Some(text.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn format_digits(value: f64, tokens: &[Token]) -> String {
+    let Some(first) = tokens.iter().position(|t| matches!(t, Token::Digit(_))) else {
+        // 数値のプレースホルダが無い書式（リテラルのみ・「標準」のみなど）
+        return tokens
+            .iter()
+            .filter_map(|t| match t {
+                Token::Literal(s) => Some(s.clone()),
+                Token::General => Some(general(value)),
+                Token::Percent => Some("%".into()),
+                _ => None,
+            })
+            .collect();
+    };
+    let last = tokens
+        .iter()
+        .rposition(|t| matches!(t, Token::Digit(_)))
+        .unwrap_or(first);
+
+    let percent = tokens.iter().filter(|t| **t == Token::Percent).count() as i32;
+    let trailing_commas = tokens[last + 1..]
+        .iter()
+        .take_while(|t| **t == Token::Comma)
+        .count() as i32;
+    let value = value * 100f64.powi(percent) / 1000f64.powi(trailing_commas);
+
+    let block = &tokens[first..=last];
+    let dot = block.iter().position(|t| *t == Token::Dot);
+    let exponent = block.iter().position(|t| matches!(t, Token::Exponent(_)));
+    let int_end = dot.or(exponent).unwrap_or(block.len());
+    let frac_end = exponent.unwrap_or(block.len());
+    let int_part = &block[..int_end];
+    let frac_part = dot.map_or(&block[0..0], |d| &block[d + 1..frac_end]);
+    let grouping = int_part.contains(&Token::Comma);
+    let frac_digits = frac_part
+        .iter()
+        .filter(|t| matches!(t, Token::Digit(_)))
+        .count();
+
+    let (mantissa, exp_text) = match exponent {
+        Some(e) => {
+            let int_digits = int_part
+                .iter()
+                .filter(|t| matches!(t, Token::Digit(_)))
+                .count()
+                .max(1) as i32;
+            let mut exp = if value == 0.0 {
+                0
+            } else {
+                value.log10().floor() as i32
+            };
+            // `##0.0E+0` のような工学表記では指数を整数部の桁数の倍数にそろえる
+            if int_digits > 1 && int_part.contains(&Token::Digit('#')) {
+                exp = exp.div_euclid(int_digits) * int_digits;
+            } else {
+                exp -= int_digits - 1;
+            }
+            let mut mantissa = value / 10f64.powi(exp);
+            if round_to(mantissa, frac_digits) >= 10f64.powi(int_digits) {
+                mantissa /= 10.0;
+                exp += 1;
+            }
+            let sign_char = match block[e] {
+                Token::Exponent('+') if exp >= 0 => "+",
+                _ if exp < 0 => "-",
+                _ => "",
+            };
+            let exp_width = block[e + 1..]
+                .iter()
+                .filter(|t| matches!(t, Token::Digit(_)))
+                .count();
+            (
+                mantissa,
+                Some(format!(
+                    "E{}{:0width$}",
+                    sign_char,
+                    exp.abs(),
+                    width = exp_width
+                )),
+            )
+        }
+        None => (value, None),
+    };
+
+    let rounded = format!("{:.*}", frac_digits, mantissa);
+    let (int_str, frac_str) = rounded.split_once('.').unwrap_or((&rounded, ""));
+    let int_str = int_str.trim_start_matches('0');
+
+    let mut out = String::new();
+    for token in &tokens[..first] {
+        push_literal(&mut out, token);
+    }
+    out.push_str(&fill_integer(int_str, int_part, grouping));
+    if dot.is_some() {
+        out.push('.');
+        out.push_str(&fill_fraction(frac_str, frac_part));
+    }
+    if let Some(exp_text) = exp_text {
+        out.push_str(&exp_text);
+    }
+    // 末尾のカンマは千単位の縮小指定なので出力しない
+    for token in &tokens[last + 1 + trailing_commas as usize..] {
+        push_literal(&mut out, token);
+    }
+    out
+}
+
+fn push_literal(out: &mut String, token: &Token) {
+    match token {
+        Token::Literal(s) => out.push_str(s),
+        Token::Percent => out.push('%'),
+        Token::Comma => out.push(','),
+        Token::Dot => out.push('.'),
+        _ => {}
+    }
+}
+
+fn round_to(value: f64, digits: usize) -> f64 {
+    format!("{:.*}", digits, value).parse().unwrap_or(value)
+}
+
+/// 整数部の数字を右から順にプレースホルダへ割り当てる（余った上位桁は先頭のプレースホルダへ）
+fn fill_integer(digits: &str, placeholders: &[Token], grouping: bool) -> String {
+    let mut remaining: Vec<char> = digits.chars().collect();
+    let slots: Vec<&Token> = placeholders
+        .iter()
+        .filter(|t| !matches!(t, Token::Comma))
+        .collect();
+    let leftmost = slots.iter().position(|t| matches!(t, Token::Digit(_)));
+
+    let mut reversed: Vec<char> = Vec::new();
+    let mut emitted = 0;
+    let mut push_digit = |reversed: &mut Vec<char>, c: char| {
+        if grouping && emitted > 0 && emitted % 3 == 0 {
+            reversed.push(',');
+        }
+        reversed.push(c);
+        emitted += 1;
+    };
+    for (idx, slot) in slots.iter().enumerate().rev() {
+        match slot {
+            Token::Digit(p) => {
+                let is_leftmost = Some(idx) == leftmost;
+                match remaining.pop() {
+                    Some(d) => push_digit(&mut reversed, d),
+                    None if *p == '0' => push_digit(&mut reversed, '0'),
+                    None if *p == '?' => reversed.push(' '),
+                    None => {}
+                }
+                if is_leftmost {
+                    while let Some(d) = remaining.pop() {
+                        push_digit(&mut reversed, d);
+                    }
+                }
+            }
+            Token::Literal(s) => reversed.extend(s.chars().rev()),
+            _ => {}
+        }
+    }
+    reversed.into_iter().rev().collect()
+}
+
+/// 小数部の数字を左から割り当て、`#` の末尾ゼロは省き `?` は空白にする
+fn fill_fraction(digits: &str, placeholders: &[Token]) -> String {
+    let digits: Vec<char> = digits.chars().collect();
+    let kinds: Vec<char> = placeholders
+        .iter()
+        .filter_map(|t| match t {
+            Token::Digit(p) => Some(*p),
+            _ => None,
+        })
+        .collect();
+    let mut keep = digits.len();
+    while keep > 0 && digits[keep - 1] == '0' && kinds[keep - 1] != '0' {
+        keep -= 1;
+    }
+
+    let mut out = String::new();
+    let mut idx = 0;
+    for token in placeholders {
+        match token {
+            Token::Digit(p) => {
+                if idx < keep {
+                    out.push(digits[idx]);
+                } else if *p == '?' {
+                    out.push(' ');
+                }
+                idx += 1;
+            }
+            Token::Literal(s) => out.push_str(s),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// シリアル値を日付・時刻として書式化する。範囲外（負の日付や 9999 年より後など）は `None`
+fn format_date(serial: f64, tokens: &[Token], date1904: bool) -> Option<String> {
+    if serial < 0.0 && !tokens.iter().any(|t| matches!(t, Token::Elapsed(..))) {
+        return None;
+    }
+    // ミリ秒への換算が i64 に収まらない値（経過時間も同じ上限で打ち切る）
+    if serial.is_nan() || serial.abs() > MAX_DATE_SERIAL {
+        return None;
+    }
+    let precision = tokens.iter().find_map(|t| match t {
+        Token::SubSecond(n) => Some(*n as u32),
+        _ => None,
+    });
+    // 秒の小数部を表示しない場合は秒単位で丸める
+    let unit = 10i64.pow(3 - precision.unwrap_or(0).min(3));
+    let total_ms = ((serial * 86_400_000.0) / unit as f64).round() as i64 * unit;
+    let days = total_ms.div_euclid(86_400_000);
+    let ms_of_day = total_ms.rem_euclid(86_400_000);
+
+    let (year, month, day, weekday) = serial_date(days, date1904)?;
+    let hour = ms_of_day / 3_600_000;
+    let minute = ms_of_day / 60_000 % 60;
+    let second = ms_of_day / 1000 % 60;
+    let twelve_hour = tokens.iter().any(|t| matches!(t, Token::AmPm(_)));
+
+    let mut out = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Literal(s) => out.push_str(s),
+            Token::Percent => out.push('%'),
+            Token::Text | Token::General => {}
+            Token::Dot => out.push('.'),
+            Token::Comma => out.push(','),
+            Token::Digit(_) | Token::Exponent(_) => {}
+            Token::Fraction => {}
+            Token::Elapsed(unit, len) => {
+                let elapsed = match unit {
+                    'h' => total_ms / 3_600_000,
+                    'm' => total_ms / 60_000,
+                    _ => total_ms / 1000,
+                };
+                if elapsed < 0 {
+                    out.push('-');
+                }
+                out.push_str(&pad(elapsed.abs(), *len));
+            }
+            Token::SubSecond(n) => {
+                let fraction = format!("{:03}", ms_of_day % 1000);
+                out.push('.');
+                out.push_str(&fraction[..(*n).min(3)]);
+            }
+            Token::AmPm(s) => {
+                let pm = hour >= 12;
+                let text = match (s.len(), pm) {
+                    (5, false) => &s[..2],
+                    (5, true) => &s[3..],
+                    (_, false) => &s[..1],
+                    (_, true) => &s[2..],
+                };
+                out.push_str(text);
+            }
+            Token::Date(part) => {
+                let len = part.len();
+                let text = match part.as_bytes()[0] {
+                    b'y' if len <= 2 => format!("{:02}", year % 100),
+                    b'y' => format!("{:04}", year),
+                    b'm' if is_minute(tokens, i) => pad(minute, len),
+                    b'm' => match len {
+                        1 | 2 => pad(month as i64, len),
+                        3 => MONTHS[month as usize - 1][..3].to_string(),
+                        4 => MONTHS[month as usize - 1].to_string(),
+                        _ => MONTHS[month as usize - 1][..1].to_string(),
+                    },
+                    b'd' => match len {
+                        1 | 2 => pad(day as i64, len),
+                        3 => WEEKDAYS[weekday][..3].to_string(),
+                        _ => WEEKDAYS[weekday].to_string(),
+                    },
+                    b'h' if twelve_hour => pad((hour + 11) % 12 + 1, len),
+                    b'h' => pad(hour, len),
+                    _ => pad(second, len),
+                };
+                out.push_str(&text);
+            }
+        }
+    }
+    Some(out)
+}
+
+fn pad(n: i64, len: usize) -> String {
+    if len >= 2 {
+        format!("{:02}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+/// `m` は直前が時、または直後が秒のとき「分」を表す
+fn is_minute(tokens: &[Token], idx: usize) -> bool {
+    let is_time = |t: &Token, unit: char| match t {
+        Token::Date(d) => d.starts_with(unit),
+        Token::Elapsed(e, _) => *e == unit,
+        _ => false,
+    };
+    let previous = tokens[..idx]
+        .iter()
+        .rev()
+        .find(|t| matches!(t, Token::Date(_) | Token::Elapsed(..)));
+    let next = tokens[idx + 1..]
+        .iter()
+        .find(|t| matches!(t, Token::Date(_) | Token::Elapsed(..)));
+    previous.is_some_and(|t| is_time(t, 'h')) || next.is_some_and(|t| is_time(t, 's'))
+}
+
+/// シリアル日数を (年, 月, 日, 曜日 0=月曜) に変換する。1900 年基準の 1900/2/29 も再現する
+fn serial_date(days: i64, date1904: bool) -> Option<(i32, u32, u32, usize)> {
+    if date1904 {
+        let date =
+            NaiveDate::from_ymd_opt(1904, 1, 1)?.checked_add_signed(Duration::try_days(days)?)?;
+        return Some((
+            date.year(),
+            date.month(),
+            date.day(),
+            date.weekday().num_days_from_monday() as usize,
+        ));
+    }
+    match days {
+        0 => Some((1900, 1, 0, 5)),
+        60 => Some((1900, 2, 29, 2)),
+        _ => {
+            let base = NaiveDate::from_ymd_opt(1899, 12, 31)?;
+            let date = base.checked_add_signed(Duration::try_days(if days > 60 {
+                days - 1
+            } else {
+                days
+            })?)?;
+            Some((
+                date.year(),
+                date.month(),
+                date.day(),
+                date.weekday().num_days_from_monday() as usize,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_numbers() {
+        assert_eq!(format_number(0.125, "0.0%", false), "12.5%");
+        assert_eq!(format_number(1200.0, "\"¥\"#,##0", false), "¥1,200");
+        assert_eq!(format_number(-1200.0, "\"¥\"#,##0", false), "-¥1,200");
+        assert_eq!(
+            format_number(-1234.5, "#,##0.00;[Red](#,##0.00)", false),
+            "(1,234.50)"
+        );
+        assert_eq!(format_number(0.0, "#,##0;-#,##0;\"-\"", false), "-");
+        assert_eq!(format_number(1234567.0, "#,##0,\"K\"", false), "1,235K");
+        assert_eq!(format_number(12345.678, "0.00E+00", false), "1.23E+04");
+        assert_eq!(format_number(1234567.0, "000-0000", false), "123-4567");
+        assert_eq!(format_number(0.5, "#.00", false), ".50");
+    }
+
+    #[test]
+    fn formats_general() {
+        assert_eq!(general(1e-7), "1E-07");
+        assert_eq!(general(123456789012.0), "1.23457E+11");
+        assert_eq!(general(1.0 / 3.0), "0.333333333");
+        assert_eq!(general(1200.0), "1200");
+    }
+
+    #[test]
+    fn formats_dates() {
+        let serial = 45366.5732638889;
+        assert_eq!(
+            format_number(serial, "yyyy/mm/dd hh:mm", false),
+            "2024/03/15 13:45"
+        );
+        assert_eq!(
+            format_number(serial, "mmm d, yyyy h:mm AM/PM", false),
+            "Mar 15, 2024 1:45 PM"
+        );
+        assert_eq!(format_number(45366.0, "yyyy-mm-dd", true), "2028-03-16");
+        assert_eq!(format_number(1.1041666667, "[h]:mm", false), "26:30");
+        assert_eq!(
+            format_number(serial, "yyyy年m月d日", false),
+            "2024年3月15日"
+        );
+        // 日付として表示できない値は General で表示する
+        assert_eq!(format_number(1e15, "yyyy-mm-dd", false), "1E+15");
+        assert_eq!(format_number(1e300, "[h]:mm", false), "1E+300");
+        assert_eq!(format_number(-1e300, "[h]:mm", false), "-1E+300");
+    }
+
+    #[test]
+    fn keeps_non_ascii_literals() {
+        assert_eq!(format_number(45000.0, "#,##0 €", false), "45,000 €");
+        assert_eq!(format_number(1.5, "0.0\"ｇ\"", false), "1.5ｇ");
+        assert_eq!(format_text("東京", "@\"都\""), "東京都");
+    }
+}
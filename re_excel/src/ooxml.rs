@@ -0,0 +1,330 @@
+use crate::model::{DefinedName, parse_address};
+use crate::numfmt;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use xml::reader::XmlEvent;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// calamine が公開しない .xlsx の付加情報（書式など）を読むための最小限のパッケージリーダー。
+/// 付加情報は補助的なものなので、部品が無い・壊れている場合は `None` として扱う
+pub struct Package<'a> {
+    zip: ZipArchive<Cursor<&'a [u8]>>,
+    /// シート名と部品パス（例: "xl/worksheets/sheet1.xml"）
+    sheets: Vec<(String, String)>,
+    /// `workbookPr/@date1904`
+    pub date1904: bool,
+}
+
+/// `.rels` の 1 エントリ。`target` はパッケージ内の絶対パス（外部参照なら URL そのまま）
+pub struct Relationship {
+    pub id: String,
+    /// 関係の種類の URI（例: ".../relationships/comments"）
+    pub rel_type: String,
+    pub target: String,
+}
+
+impl Relationship {
+    /// 種類 URI の末尾（"comments" など）で判定する
+    pub fn is(&self, kind: &str) -> bool {
+        self.rel_type.rsplit('/').next() == Some(kind)
+    }
+}
+
+/// 名前空間接頭辞を除いた要素名・属性名で保持する簡易 DOM
+#[derive(Default)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    /// 直下のテキスト（空白も保持する）
+    pub text: String,
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn children_named<'e>(&'e self, name: &'e str) -> impl Iterator<Item = &'e Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// 文字列要素（`<t>` または `<r><t>` の並び）の本文。ふりがな（`rPh`）は含めない
+    pub fn plain_text(&self) -> String {
+        let mut text = String::new();
+        for child in &self.children {
+            match child.name.as_str() {
+                "t" => text.push_str(&child.text),
+                "r" => {
+                    if let Some(t) = child.child("t") {
+                        text.push_str(&t.text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        text
+    }
+}
+
+impl<'a> Package<'a> {
+    pub fn open(bytes: &'a [u8]) -> Option<Self> {
+        let zip = ZipArchive::new(Cursor::new(bytes)).ok()?;
+        let mut package = Package {
+            zip,
+            sheets: Vec::new(),
+            date1904: false,
+        };
+
+        let workbook = package.part("xl/workbook.xml")?;
+        package.date1904 = workbook
+            .child("workbookPr")
+            .and_then(|p| p.attr("date1904"))
+            .is_some_and(|v| v == "1" || v == "true");
+        let rels = package.relationships("xl/workbook.xml");
+        for sheet in workbook
+            .child("sheets")
+            .into_iter()
+            .flat_map(|s| s.children_named("sheet"))
+        {
+            let (Some(name), Some(id)) = (sheet.attr("name"), sheet.attr("id")) else {
+                continue;
+            };
+            if let Some(rel) = rels.iter().find(|r| r.id == id) {
+                package.sheets.push((name.to_string(), rel.target.clone()));
+            }
+        }
+        Some(package)
+    }
+
+    pub fn part(&mut self, path: &str) -> Option<Element> {
+        parse_xml(&self.raw_part(path)?)
+    }
+
+    pub fn raw_part(&mut self, path: &str) -> Option<Vec<u8>> {
+        let mut file = self.zip.by_name(path).ok()?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).ok()?;
+        Some(bytes)
+    }
+
+    /// シート名に対応するワークシート部品を読む
+    pub fn sheet(&mut self, name: &str) -> Option<(String, Element)> {
+        let path = self.sheet_path(name)?.to_string();
+        let element = self.part(&path)?;
+        Some((path, element))
+    }
+
+    pub fn sheet_path(&self, name: &str) -> Option<&str> {
+        self.sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, p)| p.as_str())
+    }
+
+    /// `cellXfs` の並び順に、各セル書式の表示形式コードを返す
+    pub fn number_formats(&mut self) -> Vec<String> {
+        let Some(styles) = self.part("xl/styles.xml") else {
+            return Vec::new();
+        };
+        let custom: HashMap<&str, &str> = styles
+            .child("numFmts")
+            .into_iter()
+            .flat_map(|n| n.children_named("numFmt"))
+            .filter_map(|n| Some((n.attr("numFmtId")?, n.attr("formatCode")?)))
+            .collect();
+
+        styles
+            .child("cellXfs")
+            .into_iter()
+            .flat_map(|x| x.children_named("xf"))
+            .map(|xf| {
+                let id = xf.attr("numFmtId").unwrap_or("0");
+                custom
+                    .get(id)
+                    .copied()
+                    .or_else(|| id.parse().ok().and_then(numfmt::builtin))
+                    .unwrap_or("General")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    /// `definedNames` を読む。`localSheetId` は `<sheets>` 内の並び順で解決する
+    pub fn defined_names(&mut self) -> Vec<DefinedName> {
+        let Some(workbook) = self.part("xl/workbook.xml") else {
+            return Vec::new();
+        };
+        let sheet_names: Vec<&str> = workbook
+            .child("sheets")
+            .into_iter()
+            .flat_map(|s| s.children_named("sheet"))
+            .filter_map(|s| s.attr("name"))
+            .collect();
+
+        workbook
+            .child("definedNames")
+            .into_iter()
+            .flat_map(|d| d.children_named("definedName"))
+            .filter_map(|d| {
+                Some(DefinedName {
+                    name: d.attr("name")?.to_string(),
+                    scope: d
+                        .attr("localSheetId")
+                        .and_then(|i| i.parse::<usize>().ok())
+                        .and_then(|i| sheet_names.get(i))
+                        .map(ToString::to_string),
+                    refers_to: d.text.trim_start_matches('=').to_string(),
+                    hidden: matches!(d.attr("hidden"), Some("1" | "true")),
+                })
+            })
+            .collect()
+    }
+
+    /// `[Content_Types].xml` から部品の MIME タイプを引く（個別指定 → 拡張子の既定の順）
+    pub fn content_type(&mut self, path: &str) -> Option<String> {
+        let types = self.part("[Content_Types].xml")?;
+        let part_name = format!("/{}", path);
+        if let Some(t) = types
+            .children_named("Override")
+            .find(|o| o.attr("PartName") == Some(part_name.as_str()))
+            .and_then(|o| o.attr("ContentType"))
+        {
+            return Some(t.to_string());
+        }
+        let (_, extension) = path.rsplit_once('.')?;
+        types
+            .children_named("Default")
+            .find(|d| {
+                d.attr("Extension")
+                    .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+            })
+            .and_then(|d| d.attr("ContentType"))
+            .map(ToString::to_string)
+    }
+
+    /// 部品に付随する `_rels/*.rels` を読み、相対ターゲットを絶対パスに解決する
+    pub fn relationships(&mut self, part: &str) -> Vec<Relationship> {
+        let (dir, file) = part.rsplit_once('/').unwrap_or(("", part));
+        let rels_path = if dir.is_empty() {
+            format!("_rels/{}.rels", file)
+        } else {
+            format!("{}/_rels/{}.rels", dir, file)
+        };
+        let Some(rels) = self.part(&rels_path) else {
+            return Vec::new();
+        };
+
+        rels.children_named("Relationship")
+            .filter_map(|r| {
+                Some(Relationship {
+                    id: r.attr("Id")?.to_string(),
+                    rel_type: r.attr("Type").unwrap_or_default().to_string(),
+                    target: match r.attr("TargetMode") {
+                        Some("External") => r.attr("Target")?.to_string(),
+                        _ => resolve_path(dir, r.attr("Target")?),
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+/// 指定した部品だけを差し替えたパッケージを作る（他の部品は再圧縮せずに複製する）
+pub fn replace_parts(bytes: &[u8], parts: &[(String, Vec<u8>)]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        match parts.iter().find(|(path, _)| path == file.name()) {
+            Some((path, data)) => {
+                let options =
+                    SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+                writer.start_file(path.as_str(), options)?;
+                writer.write_all(data)?;
+            }
+            None => writer.raw_copy_file(file)?,
+        }
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+/// ワークシート内のセル（0-based の行・列）とスタイル番号 `s` の対応
+pub fn cell_styles(sheet: &Element) -> HashMap<(u32, u32), usize> {
+    sheet
+        .child("sheetData")
+        .into_iter()
+        .flat_map(|d| d.children_named("row"))
+        .flat_map(|r| r.children_named("c"))
+        .filter_map(|c| {
+            let position = parse_address(c.attr("r")?)?;
+            Some((position, c.attr("s")?.parse().ok()?))
+        })
+        .collect()
+}
+
+/// `base` ディレクトリからの相対パス（`../` を含む）をパッケージ内の絶対パスにする
+fn resolve_path(base: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut segments: Vec<&str> = base.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." | "" => {}
+            s => segments.push(s),
+        }
+    }
+    segments.join("/")
+}
+
+pub(crate) fn parse_xml(xml: &[u8]) -> Option<Element> {
+    let reader = xml::ParserConfig::new()
+        .trim_whitespace(false)
+        .whitespace_to_characters(true)
+        .cdata_to_characters(true)
+        .ignore_comments(true)
+        .coalesce_characters(true)
+        .create_reader(xml);
+
+    let mut stack: Vec<Element> = Vec::new();
+    for event in reader {
+        match event.ok()? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => stack.push(Element {
+                name: name.local_name,
+                attrs: attributes
+                    .into_iter()
+                    .map(|a| (a.name.local_name, a.value))
+                    .collect(),
+                ..Default::default()
+            }),
+            XmlEvent::EndElement { .. } => {
+                let element = stack.pop()?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Some(element),
+                }
+            }
+            XmlEvent::Characters(s) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&s);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,163 @@
+use crate::model::Workbook;
+use crate::rows::{TableMode, TableRows, rebuilt_from_rows, table_rows};
+use crate::typed::{TypedCells, ValueMode};
+use serde::ser::{self, Impossible, SerializeStruct};
+use serde::{Serialize, Serializer};
+
+/// 変換結果の出力。`Workbook` の直列化に出力モードを渡すためのアダプタで、
+/// `cells` フィールドを出力モードに合わせて差し替え、rows モードでは `table_rows` を加える。
+/// XML はルート要素での `#[serde(flatten)]` に対応しないため、フィールド単位で差し替える
+pub struct WorkbookOutput<'a> {
+    pub workbook: &'a Workbook,
+    pub value_mode: ValueMode,
+    pub table_mode: TableMode,
+}
+
+impl Serialize for WorkbookOutput<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let wb = self.workbook;
+        let rows = self.table_mode == TableMode::Rows;
+        let overrides = Overrides {
+            cells: TypedCells {
+                cells: wb
+                    .cells
+                    .iter()
+                    .filter(|c| !(rows && rebuilt_from_rows(&wb.tables, c, self.value_mode)))
+                    .collect(),
+                mode: self.value_mode,
+            },
+            table_rows: rows.then(|| table_rows(&wb.tables, &wb.cells, self.value_mode)),
+        };
+        wb.serialize(FieldOverride {
+            inner: serializer,
+            overrides: &overrides,
+        })
+    }
+}
+
+struct Overrides<'a> {
+    cells: TypedCells<'a>,
+    table_rows: Option<Vec<TableRows<'a>>>,
+}
+
+/// `Workbook` の derive(Serialize) が呼ぶ `serialize_struct` を横取りする Serializer
+struct FieldOverride<'a, S> {
+    inner: S,
+    overrides: &'a Overrides<'a>,
+}
+
+struct OverrideStruct<'a, T> {
+    inner: T,
+    overrides: &'a Overrides<'a>,
+}
+
+impl<T: SerializeStruct> SerializeStruct for OverrideStruct<'_, T> {
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    fn serialize_field<V: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), Self::Error> {
+        match key {
+            "cells" => self.inner.serialize_field(key, &self.overrides.cells),
+            _ => self.inner.serialize_field(key, value),
+        }
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(mut self) -> Result<Self::Ok, Self::Error> {
+        if let Some(table_rows) = &self.overrides.table_rows {
+            self.inner.serialize_field("table_rows", table_rows)?;
+        }
+        self.inner.end()
+    }
+}
+
+/// 構造体以外はアダプタの誤用なのでエラーにする
+macro_rules! not_a_struct {
+    ($($method:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<$ret, Self::Error> {
+                Err(ser::Error::custom("workbook output must be a struct"))
+            }
+        )*
+    };
+}
+
+impl<'a, S: Serializer> Serializer for FieldOverride<'a, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Impossible<S::Ok, S::Error>;
+    type SerializeTuple = Impossible<S::Ok, S::Error>;
+    type SerializeTupleStruct = Impossible<S::Ok, S::Error>;
+    type SerializeTupleVariant = Impossible<S::Ok, S::Error>;
+    type SerializeMap = Impossible<S::Ok, S::Error>;
+    type SerializeStruct = OverrideStruct<'a, S::SerializeStruct>;
+    type SerializeStructVariant = Impossible<S::Ok, S::Error>;
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let extra = self.overrides.table_rows.is_some() as usize;
+        Ok(OverrideStruct {
+            inner: self.inner.serialize_struct(name, len + extra)?,
+            overrides: self.overrides,
+        })
+    }
+
+    not_a_struct! {
+        serialize_bool(bool) -> S::Ok;
+        serialize_i8(i8) -> S::Ok;
+        serialize_i16(i16) -> S::Ok;
+        serialize_i32(i32) -> S::Ok;
+        serialize_i64(i64) -> S::Ok;
+        serialize_u8(u8) -> S::Ok;
+        serialize_u16(u16) -> S::Ok;
+        serialize_u32(u32) -> S::Ok;
+        serialize_u64(u64) -> S::Ok;
+        serialize_f32(f32) -> S::Ok;
+        serialize_f64(f64) -> S::Ok;
+        serialize_char(char) -> S::Ok;
+        serialize_str(&str) -> S::Ok;
+        serialize_bytes(&[u8]) -> S::Ok;
+        serialize_none() -> S::Ok;
+        serialize_unit() -> S::Ok;
+        serialize_unit_struct(&'static str) -> S::Ok;
+        serialize_unit_variant(&'static str, u32, &'static str) -> S::Ok;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<S::Ok, S::Error> {
+        Err(ser::Error::custom("workbook output must be a struct"))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> Result<S::Ok, S::Error> {
+        Err(ser::Error::custom("workbook output must be a struct"))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<S::Ok, S::Error> {
+        Err(ser::Error::custom("workbook output must be a struct"))
+    }
+}
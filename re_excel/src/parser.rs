@@ -0,0 +1,593 @@
+use crate::error::AppError;
+use crate::model::{
+    CellData, CellStyle, CellType, DefinedName, MergedRange, SheetKind, SheetMetadata,
+    SheetVisibility, SourceFormat, Workbook, col_to_letter,
+};
+use crate::{
+    comments, conditional, drawings, hyperlinks, layout, numfmt, ooxml, pivots, print, protection,
+    richtext, styles, tables, validations,
+};
+use calamine::{
+    Data, Dimensions, ExcelDateTime, ExcelDateTimeType, Ods, Range, Reader, SheetType,
+    SheetVisible, Sheets, Xls, Xlsb, Xlsx,
+};
+use chrono::NaiveTime;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Read, Seek};
+
+const OLE2_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const TEXT_SNIFF_LEN: usize = 8192;
+
+/// 変換時のオプション
+#[derive(Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// 指定されれば内容からの判別より優先する
+    pub input_format: Option<SourceFormat>,
+    /// セル書式のテーブルを出力する（.xlsx のみ）
+    pub include_styles: bool,
+    /// 画像の中身を base64 で `Drawing::data` に含める
+    pub include_media: bool,
+    /// 保護のパスワードのハッシュを含める（逆変換でパスワードを設定し直すため）
+    pub include_password_hashes: bool,
+}
+
+/// ファイル名は判別に使わない
+pub fn parse_workbook(
+    bytes: &[u8],
+    filename: Option<&str>,
+    options: ParseOptions,
+) -> Result<Workbook, AppError> {
+    match options.input_format.or_else(|| detect_format(bytes)) {
+        Some(SourceFormat::Csv) => parse_csv(bytes),
+        Some(format) => parse_excel(bytes, format, options),
+        None if looks_like_legacy_text(bytes) => Err(AppError::InvalidEncoding { cell: None }),
+        None => Err(AppError::UnrecognizedInput {
+            filename: filename.map(ToString::to_string),
+        }),
+    }
+}
+
+/// 先頭バイトからコンテナ（OLE2 / ZIP / テキスト）を判別し、ZIP は中身で細分する
+pub fn detect_format(bytes: &[u8]) -> Option<SourceFormat> {
+    if bytes.starts_with(OLE2_MAGIC) {
+        return Some(SourceFormat::Xls);
+    }
+    if !bytes.starts_with(ZIP_MAGIC) {
+        return looks_like_text(bytes).then_some(SourceFormat::Csv);
+    }
+
+    let zip = zip::ZipArchive::new(Cursor::new(bytes)).ok()?;
+    let has = |name: &str| zip.index_for_name(name).is_some();
+    if has("xl/workbook.xml") {
+        Some(SourceFormat::Xlsx)
+    } else if has("xl/workbook.bin") {
+        Some(SourceFormat::Xlsb)
+    } else if has("content.xml") {
+        Some(SourceFormat::Ods)
+    } else {
+        None
+    }
+}
+
+/// 先頭 8KiB に NUL を含まず UTF-8 として解釈できればテキストとみなす
+fn looks_like_text(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(TEXT_SNIFF_LEN)];
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        // 8KiB 境界で多バイト文字が切れた場合のみ許容する
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// UTF-8 ではないが制御文字を含まない（Shift_JIS などのテキストとみられる）
+fn looks_like_legacy_text(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(TEXT_SNIFF_LEN)];
+    !head.is_empty()
+        && head
+            .iter()
+            .all(|&b| b >= 0x20 || matches!(b, b'\t' | b'\n' | b'\r'))
+}
+
+fn parse_csv(bytes: &[u8]) -> Result<Workbook, AppError> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(Cursor::new(bytes));
+    let mut cells = Vec::new();
+
+    for (row_idx, record) in rdr.records().enumerate() {
+        let record = record.map_err(|e| csv_error(row_idx, e))?;
+        for (col_idx, field) in record.iter().enumerate() {
+            let address = format!(
+                "{}{}",
+                col_to_letter((col_idx + 1) as u32),
+                row_idx as u32 + 1
+            );
+            cells.push(CellData {
+                sheet: "Sheet1".into(),
+                address: address.clone(),
+                row: row_idx as u32 + 1,
+                col: col_idx as u32 + 1,
+                data_type: CellType::String,
+                value: field.to_string(),
+                formula: None,
+                serial: None,
+                number_format: None,
+                display_text: None,
+                style_id: None,
+                runs: Vec::new(),
+                phonetic: Vec::new(),
+                pivot_table: None,
+                locked: true,
+            });
+        }
+    }
+
+    Ok(Workbook {
+        source_format: Some(SourceFormat::Csv),
+        sheets: vec![SheetMetadata {
+            name: "Sheet1".into(),
+            index: 0,
+            hidden: false,
+            visibility: SheetVisibility::Visible,
+            sheet_type: SheetKind::Worksheet,
+            ..Default::default()
+        }],
+        cells,
+        ..Default::default()
+    })
+}
+
+/// CSV のエラー位置をセル番地に変換する（UTF-8 エラー時のみ列まで特定できる）
+fn csv_error(row_idx: usize, e: csv::Error) -> AppError {
+    match e.kind() {
+        csv::ErrorKind::Utf8 { err, .. } => AppError::InvalidEncoding {
+            cell: Some(format!(
+                "{}{}",
+                col_to_letter(err.field() as u32 + 1),
+                row_idx + 1
+            )),
+        },
+        _ => AppError::SheetRead {
+            sheet: "Sheet1".into(),
+            cell: None,
+            message: e.to_string(),
+        },
+    }
+}
+
+fn parse_excel(
+    bytes: &[u8],
+    format: SourceFormat,
+    options: ParseOptions,
+) -> Result<Workbook, AppError> {
+    let cursor = Cursor::new(bytes);
+    let opened = match format {
+        SourceFormat::Xlsx => Xlsx::new(cursor)
+            .map(Sheets::Xlsx)
+            .map_err(|e| e.to_string()),
+        SourceFormat::Xls => Xls::new(cursor).map(Sheets::Xls).map_err(|e| e.to_string()),
+        SourceFormat::Xlsb => Xlsb::new(cursor)
+            .map(Sheets::Xlsb)
+            .map_err(|e| e.to_string()),
+        SourceFormat::Ods => Ods::new(cursor).map(Sheets::Ods).map_err(|e| e.to_string()),
+        SourceFormat::Csv => return parse_csv(bytes),
+    };
+    let mut excel = opened.map_err(|message| AppError::CorruptContainer {
+        format: format.as_str().to_string(),
+        message,
+    })?;
+
+    let mut sheets = Vec::new();
+    let mut cells = Vec::new();
+    let mut merged_ranges = Vec::new();
+    let mut comments = Vec::new();
+    let mut hyperlinks = Vec::new();
+    let mut tables = Vec::new();
+    let mut data_validations = Vec::new();
+    let mut conditional_formats = Vec::new();
+    let mut drawings = Vec::new();
+    let mut charts = Vec::new();
+    let mut pivot_tables = Vec::new();
+
+    // 表示形式など calamine が公開しない情報は .xlsx の部品を直接読む
+    let mut package = match format {
+        SourceFormat::Xlsx => ooxml::Package::open(bytes),
+        _ => None,
+    };
+    let number_formats = package.as_mut().map(|p| p.number_formats());
+    let mut date1904 = package.as_ref().map(|p| p.date1904);
+    let xf_styles = match package.as_mut() {
+        Some(p) if options.include_styles => styles::read_cell_styles(p),
+        _ => Vec::new(),
+    };
+    // セルのロックは書式の出力を求められていなくても、保護の設定だけを読む
+    let xf_locks = package
+        .as_mut()
+        .map(styles::read_cell_locks)
+        .unwrap_or_default();
+    let mut styles = Vec::new();
+    let mut style_ids = HashMap::new();
+    let colors = package.as_mut().map(styles::Colors::read);
+    let shared_strings = match (package.as_mut(), &colors) {
+        (Some(p), Some(colors)) => richtext::read_shared_strings(p, colors),
+        _ => Vec::new(),
+    };
+
+    let metadata = excel.sheets_metadata().to_vec();
+    for (idx, sheet) in metadata.iter().enumerate() {
+        let name = &sheet.name;
+        let visibility = match sheet.visible {
+            SheetVisible::Visible => SheetVisibility::Visible,
+            SheetVisible::Hidden => SheetVisibility::Hidden,
+            SheetVisible::VeryHidden => SheetVisibility::VeryHidden,
+        };
+        let sheet_type = match sheet.typ {
+            SheetType::WorkSheet => SheetKind::Worksheet,
+            SheetType::ChartSheet => SheetKind::ChartSheet,
+            SheetType::MacroSheet => SheetKind::MacroSheet,
+            SheetType::DialogSheet => SheetKind::DialogSheet,
+            SheetType::Vba => SheetKind::Vba,
+        };
+        sheets.push(SheetMetadata {
+            name: name.clone(),
+            index: idx,
+            hidden: visibility != SheetVisibility::Visible,
+            visibility,
+            sheet_type,
+            ..Default::default()
+        });
+
+        if let Some(p) = package.as_mut()
+            && let Some(path) = p.sheet_path(name).map(ToString::to_string)
+        {
+            let (sheet_drawings, sheet_charts) =
+                drawings::read_drawings(p, name, &path, options.include_media);
+            drawings.extend(sheet_drawings);
+            charts.extend(sheet_charts);
+        }
+
+        // チャートシートにはセルが存在しない
+        if sheet_type == SheetKind::ChartSheet {
+            continue;
+        }
+
+        let sheet_error = |message: String| AppError::SheetRead {
+            sheet: name.clone(),
+            cell: None,
+            message,
+        };
+        let range = reader_panic(|| excel.worksheet_range(name))
+            .map_err(sheet_error)?
+            .map_err(|e| sheet_error(e.to_string()))?;
+        let formulas = reader_panic(|| excel.worksheet_formula(name))
+            .map_err(sheet_error)?
+            .map_err(|e| sheet_error(format!("formulas: {}", e)))?;
+        let sheet_xml = package.as_mut().and_then(|p| p.sheet(name));
+        let cell_styles = sheet_xml
+            .as_ref()
+            .map(|(_, xml)| ooxml::cell_styles(xml))
+            .unwrap_or_default();
+        let mut rich_text = match (&sheet_xml, &colors) {
+            (Some((_, xml)), Some(colors)) => {
+                richtext::cell_rich_text(xml, &shared_strings, colors)
+            }
+            _ => HashMap::new(),
+        };
+        let sheet_pivots = match (package.as_mut(), &sheet_xml) {
+            (Some(p), Some((path, _))) => pivots::read_pivot_tables(p, name, path),
+            _ => Vec::new(),
+        };
+        if let (Some(p), Some((path, xml))) = (package.as_mut(), &sheet_xml) {
+            comments.extend(comments::read_comments(p, name, path));
+            hyperlinks.extend(hyperlinks::read_hyperlinks(p, name, path, xml));
+            tables.extend(tables::read_tables(p, name, path));
+            data_validations.extend(validations::read_validations(name, xml));
+        }
+        if let (Some((_, xml)), Some(metadata)) = (&sheet_xml, sheets.last_mut()) {
+            metadata.protection =
+                protection::read_sheet_protection(xml, options.include_password_hashes);
+        }
+        if let (Some(colors), Some((_, xml))) = (&colors, &sheet_xml) {
+            conditional_formats.extend(conditional::read_conditional_formats(name, xml, colors));
+            if let Some(metadata) = sheets.last_mut() {
+                layout::read_layout(metadata, xml, colors);
+                metadata.print_setup = print::read_print_setup(xml);
+            }
+        }
+        let merges = merge_cells(&mut excel, name)
+            .map_err(|e| sheet_error(format!("merged cells: {}", e)))?;
+
+        // 左上セルが空でも結合範囲はそのまま出力する
+        for dims in merges {
+            merged_ranges.push(MergedRange {
+                sheet: name.clone(),
+                start: format!("{}{}", col_to_letter(dims.start.1 + 1), dims.start.0 + 1),
+                end: format!("{}{}", col_to_letter(dims.end.1 + 1), dims.end.0 + 1),
+            });
+        }
+
+        // 値と数式を絶対座標で突き合わせる（キャッシュ値のない数式セルも出力する）
+        let mut sheet_cells: BTreeMap<_, (Option<&Data>, Option<String>)> = BTreeMap::new();
+        for (pos, v) in absolute_cells(&range) {
+            sheet_cells.entry(pos).or_default().0 = Some(v);
+        }
+        for (pos, f) in absolute_cells(&formulas) {
+            sheet_cells.entry(pos).or_default().1 = Some(f.clone());
+        }
+        // 書式だけが設定された空セル（罫線・塗りつぶしなど）も出力する
+        if options.include_styles {
+            for (&pos, &s) in &cell_styles {
+                if s != 0 {
+                    sheet_cells.entry(pos).or_default();
+                }
+            }
+        }
+
+        let pivot_areas: Vec<_> = sheet_pivots
+            .iter()
+            .filter_map(pivots::PivotArea::new)
+            .collect();
+        for ((r, c), (v, formula)) in sheet_cells {
+            let address = format!("{}{}", col_to_letter(c + 1), r + 1);
+            let mut serial = None;
+            let (data_type, value) = match v.unwrap_or(&Data::Empty) {
+                Data::Empty => (CellType::Empty, String::new()),
+                Data::String(s) => (CellType::String, s.clone()),
+                Data::Float(f) => (CellType::Number, f.to_string()),
+                Data::Int(i) => (CellType::Number, i.to_string()),
+                Data::Bool(b) => (CellType::Boolean, b.to_string()),
+                Data::Error(e) => (CellType::Error, format!("{:?}", e)),
+                Data::DateTime(dt) => {
+                    serial = Some(dt.as_f64());
+                    if dt.is_datetime() {
+                        date1904.get_or_insert(is_1904(dt));
+                    }
+                    excel_datetime(dt)
+                }
+                Data::DateTimeIso(s) => (CellType::DateTimeIso, s.clone()),
+                Data::DurationIso(s) => (CellType::DurationIso, s.clone()),
+            };
+            let mut cell = CellData {
+                sheet: name.clone(),
+                address,
+                row: r + 1,
+                col: c + 1,
+                data_type,
+                value,
+                formula,
+                serial,
+                number_format: None,
+                display_text: None,
+                style_id: None,
+                runs: Vec::new(),
+                phonetic: Vec::new(),
+                pivot_table: pivot_areas
+                    .iter()
+                    .find(|a| a.covers(r, c))
+                    .map(|a| a.name.to_string()),
+                locked: true,
+            };
+            if let Some(text) = rich_text.remove(&(r, c)) {
+                cell.runs = text.runs;
+                cell.phonetic = text.phonetic;
+            }
+            let xf = cell_styles.get(&(r, c)).copied().unwrap_or(0);
+            cell.locked = xf_locks.get(xf).copied().unwrap_or(true);
+            if let Some(style) = xf_styles.get(xf) {
+                cell.style_id = Some(
+                    *style_ids
+                        .entry(xf)
+                        .or_insert_with(|| intern_style(&mut styles, style)),
+                );
+            }
+            if let Some(formats) = &number_formats {
+                let code = cell_styles
+                    .get(&(r, c))
+                    .and_then(|&s| formats.get(s))
+                    .map_or("General", String::as_str);
+                cell.display_text =
+                    Some(numfmt::display_text(&cell, code, date1904.unwrap_or(false)));
+                if code != "General" {
+                    cell.number_format = Some(code.to_string());
+                }
+            }
+            cells.push(cell);
+        }
+        pivot_tables.extend(sheet_pivots);
+    }
+
+    // calamine の名前一覧にはスコープと非表示フラグが無いので、.xlsx は部品から読む
+    let defined_names = match package.as_mut() {
+        Some(p) => p.defined_names(),
+        None => excel
+            .defined_names()
+            .iter()
+            .map(|(name, formula)| DefinedName {
+                name: name.clone(),
+                scope: None,
+                refers_to: formula.trim_start_matches('=').to_string(),
+                hidden: false,
+            })
+            .collect(),
+    };
+
+    print::apply_print_names(&mut sheets, &defined_names);
+
+    // 差分書式は条件付き書式がある場合のみ出力する
+    let differential_styles = match package.as_mut() {
+        Some(p) if !conditional_formats.is_empty() => styles::read_differential_styles(p),
+        _ => Vec::new(),
+    };
+
+    Ok(Workbook {
+        source_format: Some(format),
+        sheets,
+        cells,
+        merged_ranges,
+        date1904,
+        styles,
+        comments,
+        hyperlinks,
+        defined_names,
+        tables,
+        data_validations,
+        conditional_formats,
+        differential_styles,
+        drawings,
+        charts,
+        pivot_tables,
+        protection: package
+            .as_mut()
+            .and_then(|p| protection::read_workbook_protection(p, options.include_password_hashes)),
+    })
+}
+
+/// 内容が同じ書式は 1 つの id にまとめる
+fn intern_style(styles: &mut Vec<CellStyle>, style: &CellStyle) -> usize {
+    if let Some(existing) = styles.iter().find(|s| {
+        **s == CellStyle {
+            id: s.id,
+            ..style.clone()
+        }
+    }) {
+        return existing.id;
+    }
+    let id = styles.len();
+    styles.push(CellStyle {
+        id,
+        ..style.clone()
+    });
+    id
+}
+
+/// シリアル値を ISO 8601 に変換する。日付のみ・時刻のみの値はそれぞれの表記にする
+fn excel_datetime(dt: &ExcelDateTime) -> (CellType, String) {
+    if dt.is_duration() {
+        return (CellType::Duration, iso_duration(dt.as_f64()));
+    }
+    let Some(datetime) = in_date_range(dt).then(|| dt.as_datetime()).flatten() else {
+        return (CellType::DateTime, dt.as_f64().to_string());
+    };
+    let value = if dt.as_f64().abs() < 1.0 {
+        datetime.format("%H:%M:%S%.f").to_string()
+    } else if datetime.time() == NaiveTime::MIN {
+        datetime.format("%Y-%m-%d").to_string()
+    } else {
+        datetime.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+    };
+    (CellType::DateTime, value)
+}
+
+/// calamine は .xls / .xlsb のブックのフラグ（DATE1904 / BrtWbProp）を公開せず、
+/// 日付セルごとに持たせているため、1900 年基準で換算した結果と比べて取り出す
+fn is_1904(dt: &ExcelDateTime) -> bool {
+    in_date_range(dt)
+        && ExcelDateTime::new(dt.as_f64(), ExcelDateTimeType::DateTime, false).as_datetime()
+            != dt.as_datetime()
+}
+
+/// calamine の日時への換算は、範囲外の値（壊れたセルの `1e300` など）で panic する
+fn in_date_range(dt: &ExcelDateTime) -> bool {
+    dt.as_f64().abs() <= numfmt::MAX_DATE_SERIAL
+}
+
+/// 日数を `PT26H30M` のような ISO 8601 の期間表記にする（ミリ秒単位で丸める）
+fn iso_duration(days: f64) -> String {
+    let total_ms = (days * 86_400_000.0).round() as i64;
+    let sign = if total_ms < 0 { "-" } else { "" };
+    let ms = total_ms.unsigned_abs();
+    let (hours, minutes, seconds, millis) =
+        (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000);
+
+    let mut s = format!("{}PT", sign);
+    if hours > 0 {
+        s.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        s.push_str(&format!("{}M", minutes));
+    }
+    if millis > 0 {
+        s.push_str(&format!("{}.{:03}S", seconds, millis));
+    } else if seconds > 0 || (hours == 0 && minutes == 0) {
+        s.push_str(&format!("{}S", seconds));
+    }
+    s
+}
+
+/// calamine は壊れた部品（範囲外の共有文字列番号など）を読むと panic することがある。
+/// 入力の不備なので、サーバーエラーではなくシートの読み取りエラーにする
+fn reader_panic<T>(read: impl FnOnce() -> T) -> Result<T, String> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(read)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        format!("malformed sheet data: {}", message)
+    })
+}
+
+/// 結合セル情報は xlsx / xls のみ calamine から取得できる
+fn merge_cells<RS: Read + Seek>(
+    excel: &mut Sheets<RS>,
+    name: &str,
+) -> Result<Vec<Dimensions>, String> {
+    match excel {
+        Sheets::Xlsx(xlsx) => xlsx
+            .worksheet_merge_cells(name)
+            .transpose()
+            .map(Option::unwrap_or_default)
+            .map_err(|e| e.to_string()),
+        Sheets::Xls(xls) => Ok(xls.worksheet_merge_cells(name).unwrap_or_default()),
+        Sheets::Xlsb(_) | Sheets::Ods(_) => Ok(Vec::new()),
+    }
+}
+
+/// Range 内の使用セル（既定値以外）を 0-based の絶対座標付きで列挙する
+fn absolute_cells<T: calamine::CellType>(
+    range: &Range<T>,
+) -> impl Iterator<Item = ((u32, u32), &T)> {
+    let (row0, col0) = range.start().unwrap_or((0, 0));
+    range
+        .used_cells()
+        .map(move |(r, c, v)| ((row0 + r as u32, col0 + c as u32), v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_dates_as_iso_8601() {
+        let date = |serial, is_1904| {
+            excel_datetime(&ExcelDateTime::new(
+                serial,
+                ExcelDateTimeType::DateTime,
+                is_1904,
+            ))
+        };
+        assert_eq!(date(45366.0, false).1, "2024-03-15");
+        assert_eq!(date(45366.0, true).1, "2028-03-16");
+        assert_eq!(date(45366.5, false).1, "2024-03-15T12:00:00");
+        assert_eq!(date(0.25, false).1, "06:00:00");
+        // 日付として表せない値はシリアル値のまま
+        assert_eq!(date(1e7, false).1, "10000000");
+        assert!(!is_1904(&ExcelDateTime::new(
+            1e300,
+            ExcelDateTimeType::DateTime,
+            true
+        )));
+    }
+
+    #[test]
+    fn formats_durations_as_iso_8601() {
+        assert_eq!(iso_duration(1.1041666667), "PT26H30M");
+        assert_eq!(iso_duration(0.0), "PT0S");
+        assert_eq!(iso_duration(-0.5), "-PT12H");
+        assert_eq!(iso_duration(1.5 / 86_400.0), "PT1.500S");
+    }
+}
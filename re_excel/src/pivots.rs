@@ -0,0 +1,120 @@
+use crate::model::{PivotDataField, PivotTable, parse_address};
+use crate::ooxml::{Element, Package};
+
+/// 行・列フィールドの並びで、複数の値フィールドの位置を表す番号
+const VALUES_FIELD: i64 = -2;
+
+/// ワークシートに関連付けられたピボットテーブル部品を読む。
+/// フィールド名と集計元は、ピボットテーブルが参照するキャッシュ定義から引く
+pub fn read_pivot_tables(package: &mut Package, sheet: &str, sheet_path: &str) -> Vec<PivotTable> {
+    let targets: Vec<String> = package
+        .relationships(sheet_path)
+        .into_iter()
+        .filter(|r| r.is("pivotTable"))
+        .map(|r| r.target)
+        .collect();
+
+    targets
+        .iter()
+        .filter_map(|target| {
+            let definition = package.part(target)?;
+            let cache = package
+                .relationships(target)
+                .into_iter()
+                .find(|r| r.is("pivotCacheDefinition"))
+                .and_then(|r| package.part(&r.target));
+            read_pivot_table(&definition, cache.as_ref(), sheet)
+        })
+        .collect()
+}
+
+/// ピボットテーブルの出力範囲（0-based の行・列）。セルごとに範囲の文字列を解析しないよう、
+/// シートを読む前に 1 度だけ作る
+pub struct PivotArea<'a> {
+    pub name: &'a str,
+    first: (u32, u32),
+    last: (u32, u32),
+}
+
+impl<'a> PivotArea<'a> {
+    pub fn new(pivot: &'a PivotTable) -> Option<Self> {
+        let mut corners = pivot.range.split(':');
+        let first = corners.next().and_then(parse_address)?;
+        let last = corners.next().and_then(parse_address).unwrap_or(first);
+        Some(PivotArea {
+            name: &pivot.name,
+            first,
+            last,
+        })
+    }
+
+    pub fn covers(&self, row: u32, col: u32) -> bool {
+        (self.first.0..=self.last.0).contains(&row) && (self.first.1..=self.last.1).contains(&col)
+    }
+}
+
+fn read_pivot_table(
+    definition: &Element,
+    cache: Option<&Element>,
+    sheet: &str,
+) -> Option<PivotTable> {
+    let field_names: Vec<&str> = cache
+        .and_then(|c| c.child("cacheFields"))
+        .into_iter()
+        .flat_map(|f| f.children_named("cacheField"))
+        .map(|f| f.attr("name").unwrap_or_default())
+        .collect();
+    let values_caption = definition.attr("dataCaption").unwrap_or("Values");
+    let field_name = |index: i64| -> Option<String> {
+        if index == VALUES_FIELD {
+            return Some(values_caption.to_string());
+        }
+        let name = field_names.get(usize::try_from(index).ok()?)?;
+        Some(name.to_string())
+    };
+    let fields = |list: &str, item: &str, attr: &str| -> Vec<String> {
+        definition
+            .child(list)
+            .into_iter()
+            .flat_map(|l| l.children_named(item))
+            .filter_map(|f| f.attr(attr)?.parse().ok())
+            .filter_map(field_name)
+            .collect()
+    };
+
+    let source = cache.and_then(|c| c.child("cacheSource"));
+    let worksheet_source = source.and_then(|s| s.child("worksheetSource"));
+    let source_attr = |name: &str| {
+        worksheet_source
+            .and_then(|w| w.attr(name))
+            .map(ToString::to_string)
+    };
+
+    Some(PivotTable {
+        name: definition.attr("name")?.to_string(),
+        sheet: sheet.to_string(),
+        range: definition.child("location")?.attr("ref")?.to_string(),
+        source_type: source
+            .and_then(|s| s.attr("type"))
+            .unwrap_or("worksheet")
+            .to_string(),
+        source_sheet: source_attr("sheet"),
+        source_range: source_attr("ref"),
+        source_name: source_attr("name"),
+        row_fields: fields("rowFields", "field", "x"),
+        column_fields: fields("colFields", "field", "x"),
+        page_fields: fields("pageFields", "pageField", "fld"),
+        data_fields: definition
+            .child("dataFields")
+            .into_iter()
+            .flat_map(|d| d.children_named("dataField"))
+            .filter_map(|d| {
+                Some(PivotDataField {
+                    name: d.attr("name").map(ToString::to_string),
+                    field: field_name(d.attr("fld")?.parse().ok()?)?,
+                    function: d.attr("subtotal").unwrap_or("sum").to_string(),
+                })
+            })
+            .collect(),
+    })
+}
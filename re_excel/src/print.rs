@@ -0,0 +1,193 @@
+use crate::model::{DefinedName, PageMargins, PrintSetup, SheetMetadata};
+use crate::ooxml::Element;
+
+/// ワークシート部品の印刷設定を読む（すべて既定値なら無し）。印刷範囲・印刷タイトルは
+/// 定義名にあるので `apply_print_names` で補う
+pub fn read_print_setup(sheet_xml: &Element) -> Option<PrintSetup> {
+    let mut setup = PrintSetup::default();
+
+    if let Some(page) = sheet_xml.child("pageSetup") {
+        let number = |name: &str| page.attr(name).and_then(|v| v.parse::<u32>().ok());
+        setup.orientation = page
+            .attr("orientation")
+            .filter(|o| *o == "landscape")
+            .map(ToString::to_string);
+        setup.paper_size = number("paperSize");
+        setup.scale = number("scale").filter(|s| *s != 100);
+        let fit_to_page = sheet_xml
+            .child("sheetPr")
+            .and_then(|p| p.child("pageSetUpPr"))
+            .is_some_and(|p| matches!(p.attr("fitToPage"), Some("1" | "true")));
+        if fit_to_page {
+            setup.fit_to_width = Some(number("fitToWidth").unwrap_or(1));
+            setup.fit_to_height = Some(number("fitToHeight").unwrap_or(1));
+        }
+    }
+
+    if let Some(margins) = sheet_xml.child("pageMargins") {
+        let defaults = PageMargins::default();
+        let margin = |name: &str, default: f64| {
+            margins
+                .attr(name)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        let margins = PageMargins {
+            left: margin("left", defaults.left),
+            right: margin("right", defaults.right),
+            top: margin("top", defaults.top),
+            bottom: margin("bottom", defaults.bottom),
+            header: margin("header", defaults.header),
+            footer: margin("footer", defaults.footer),
+        };
+        setup.margins = (margins != defaults).then_some(margins);
+    }
+
+    if let Some(header_footer) = sheet_xml.child("headerFooter") {
+        let text = |name: &str| {
+            header_footer
+                .child(name)
+                .map(|e| e.text.clone())
+                .filter(|t| !t.is_empty())
+        };
+        setup.header = text("oddHeader");
+        setup.footer = text("oddFooter");
+    }
+
+    let breaks = |name: &str| -> Vec<u32> {
+        sheet_xml
+            .child(name)
+            .into_iter()
+            .flat_map(|b| b.children_named("brk"))
+            .filter_map(|b| b.attr("id")?.parse().ok())
+            .collect()
+    };
+    setup.row_breaks = breaks("rowBreaks");
+    setup.col_breaks = breaks("colBreaks");
+
+    (setup != PrintSetup::default()).then_some(setup)
+}
+
+/// シートスコープの `_xlnm.Print_Area` / `_xlnm.Print_Titles` を各シートの印刷設定に反映する
+pub fn apply_print_names(sheets: &mut [SheetMetadata], names: &[DefinedName]) {
+    for name in names {
+        let Some(sheet) = name
+            .scope
+            .as_ref()
+            .and_then(|scope| sheets.iter_mut().find(|s| &s.name == scope))
+        else {
+            continue;
+        };
+        let areas = areas(&name.refers_to);
+        match name.name.as_str() {
+            "_xlnm.Print_Area" if !areas.is_empty() => {
+                let setup = sheet.print_setup.get_or_insert_with(Default::default);
+                setup.print_area = Some(areas.join(" "));
+            }
+            "_xlnm.Print_Titles" => {
+                let setup = sheet.print_setup.get_or_insert_with(Default::default);
+                for area in areas {
+                    // 行は "1:2"、列は "A:B" の形になる
+                    if area.starts_with(|c: char| c.is_ascii_digit()) {
+                        setup.repeat_rows = Some(area);
+                    } else {
+                        setup.repeat_cols = Some(area);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `'Sheet 1'!$A$1:$B$2,'Sheet 1'!$D$1` をシート名と `$` を除いた範囲の並びにする
+fn areas(refers_to: &str) -> Vec<String> {
+    let mut areas = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in refers_to.chars() {
+        match c {
+            '\'' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            ',' if !quoted => areas.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    areas.push(current);
+
+    areas
+        .iter()
+        .map(|area| {
+            // シート名に '!' が含まれていても最後の '!' 以降が範囲になる
+            let range = area.rsplit_once('!').map_or(area.as_str(), |(_, r)| r);
+            range.replace('$', "")
+        })
+        .filter(|area| !area.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_page_setup() {
+        let xml = r#"<worksheet><sheetPr><pageSetUpPr fitToPage="1"/></sheetPr><pageMargins left="0.5" right="0.7" top="0.75" bottom="0.75" header="0.3" footer="0.3"/><pageSetup paperSize="9" orientation="landscape" fitToHeight="0"/><headerFooter><oddHeader>&amp;CTitle</oddHeader></headerFooter><rowBreaks count="2"><brk id="20" max="16383" man="1"/><brk id="40" max="16383" man="1"/></rowBreaks></worksheet>"#;
+        let setup = read_print_setup(&crate::ooxml::parse_xml(xml.as_bytes()).unwrap()).unwrap();
+        assert_eq!(setup.orientation.as_deref(), Some("landscape"));
+        assert_eq!(setup.paper_size, Some(9));
+        assert_eq!(
+            (setup.fit_to_width, setup.fit_to_height),
+            (Some(1), Some(0))
+        );
+        assert_eq!(setup.margins.unwrap().left, 0.5);
+        assert_eq!(setup.header.as_deref(), Some("&CTitle"));
+        assert_eq!(setup.footer, None);
+        assert_eq!(setup.row_breaks, [20, 40]);
+
+        let xml = r#"<worksheet><pageMargins left="0.7" right="0.7" top="0.75" bottom="0.75" header="0.3" footer="0.3"/><pageSetup orientation="portrait"/></worksheet>"#;
+        assert_eq!(
+            read_print_setup(&crate::ooxml::parse_xml(xml.as_bytes()).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn applies_print_area_and_titles() {
+        let mut sheets = ["Q1, Sales!", "Rows"].map(|name| SheetMetadata {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        let name = |name: &str, scope: &str, refers_to: &str| DefinedName {
+            name: name.to_string(),
+            scope: Some(scope.to_string()),
+            refers_to: refers_to.to_string(),
+            hidden: false,
+        };
+        apply_print_names(
+            &mut sheets,
+            &[
+                // 引用符で囲まれたシート名の中の ',' と '!' で範囲を区切らない
+                name(
+                    "_xlnm.Print_Titles",
+                    "Q1, Sales!",
+                    "'Q1, Sales!'!$A:$B,'Q1, Sales!'!$1:$2",
+                ),
+                name("_xlnm.Print_Area", "Q1, Sales!", "'Q1, Sales!'!$A$1:$F$40"),
+                name("_xlnm.Print_Titles", "Rows", "Rows!$3:$3"),
+                name("TaxRate", "Rows", "Rows!$B$1"),
+            ],
+        );
+
+        let setup = sheets[0].print_setup.as_ref().unwrap();
+        assert_eq!(setup.print_area.as_deref(), Some("A1:F40"));
+        assert_eq!(setup.repeat_rows.as_deref(), Some("1:2"));
+        assert_eq!(setup.repeat_cols.as_deref(), Some("A:B"));
+        let setup = sheets[1].print_setup.as_ref().unwrap();
+        assert_eq!(setup.repeat_rows.as_deref(), Some("3:3"));
+        assert_eq!(setup.repeat_cols, None);
+        assert_eq!(setup.print_area, None);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::model::{PasswordHash, SheetProtection, WorkbookProtection};
+use crate::ooxml::{Element, Package};
+
+/// パスワードのハッシュを表す属性名（アルゴリズム・ハッシュ値・ソルト・反復回数・旧形式のハッシュ）
+pub type HashAttributes = [&'static str; 5];
+
+pub const SHEET_HASH: HashAttributes = [
+    "algorithmName",
+    "hashValue",
+    "saltValue",
+    "spinCount",
+    "password",
+];
+
+/// ブックの保護では属性名に `workbook` が前置される
+pub const WORKBOOK_HASH: HashAttributes = [
+    "workbookAlgorithmName",
+    "workbookHashValue",
+    "workbookSaltValue",
+    "workbookSpinCount",
+    "workbookPassword",
+];
+
+/// `workbookProtection` を読む。構造・ウィンドウのどちらもロックされていなければ `None`。
+/// パスワードのハッシュは `include_hashes` のときだけ持たせる
+pub fn read_workbook_protection(
+    package: &mut Package,
+    include_hashes: bool,
+) -> Option<WorkbookProtection> {
+    let workbook = package.part("xl/workbook.xml")?;
+    let element = workbook.child("workbookProtection")?;
+    let password = password_hash(element, &WORKBOOK_HASH);
+    let protection = WorkbookProtection {
+        structure: is_set(element, "lockStructure"),
+        windows: is_set(element, "lockWindows"),
+        has_password: password.is_some(),
+        password: password.filter(|_| include_hashes),
+    };
+    // パスワードだけが設定されたブックも保護されているものとして報告する
+    (protection.structure || protection.windows || protection.has_password).then_some(protection)
+}
+
+/// ワークシートの `sheetProtection` を読む。`sheet` 属性が立っていなければ保護されていない
+pub fn read_sheet_protection(sheet: &Element, include_hashes: bool) -> Option<SheetProtection> {
+    let element = sheet.child("sheetProtection")?;
+    if !is_set(element, "sheet") {
+        return None;
+    }
+    // 属性は「保護する（禁止する）」かどうかを表す。既定値は操作ごとに異なる
+    let allowed = |name: &str, protected_by_default: bool| match element.attr(name) {
+        Some(v) => !matches!(v, "1" | "true"),
+        None => !protected_by_default,
+    };
+    let password = password_hash(element, &SHEET_HASH);
+    Some(SheetProtection {
+        has_password: password.is_some(),
+        password: password.filter(|_| include_hashes),
+        select_locked_cells: allowed("selectLockedCells", false),
+        select_unlocked_cells: allowed("selectUnlockedCells", false),
+        format_cells: allowed("formatCells", true),
+        format_columns: allowed("formatColumns", true),
+        format_rows: allowed("formatRows", true),
+        insert_columns: allowed("insertColumns", true),
+        insert_rows: allowed("insertRows", true),
+        insert_hyperlinks: allowed("insertHyperlinks", true),
+        delete_columns: allowed("deleteColumns", true),
+        delete_rows: allowed("deleteRows", true),
+        sort: allowed("sort", true),
+        auto_filter: allowed("autoFilter", true),
+        pivot_tables: allowed("pivotTables", true),
+        edit_objects: allowed("objects", false),
+        edit_scenarios: allowed("scenarios", false),
+    })
+}
+
+fn password_hash(element: &Element, names: &HashAttributes) -> Option<PasswordHash> {
+    let [algorithm, hash, salt, spin_count, legacy] = names.map(|name| {
+        element
+            .attr(name)
+            .filter(|v| !v.is_empty())
+            .map(ToString::to_string)
+    });
+    if let Some(hash) = hash {
+        return Some(PasswordHash {
+            algorithm,
+            hash,
+            salt,
+            spin_count: spin_count.and_then(|c| c.parse().ok()),
+        });
+    }
+    // 旧形式のハッシュ "0000" はパスワード無しと同じ
+    let legacy = legacy.filter(|h| !h.trim_start_matches('0').is_empty())?;
+    Some(PasswordHash {
+        algorithm: None,
+        hash: legacy,
+        salt: None,
+        spin_count: None,
+    })
+}
+
+fn is_set(element: &Element, name: &str) -> bool {
+    matches!(element.attr(name), Some("1" | "true"))
+}
@@ -0,0 +1,1879 @@
+use crate::drawings::base64_decode;
+use crate::error::{AppError, ErrorLocation};
+use crate::model::{
+    BorderEdge, CellData, CellStyle, CellType, Chart, ConditionalFormat, ConditionalThreshold,
+    DataValidation, DefinedName, Drawing, DrawingKind, PasswordHash, PrintSetup, SheetMetadata,
+    SheetProtection, SheetVisibility, Table, Workbook, col_to_letter, error_literal, parse_address,
+};
+use crate::ooxml::{self, Package};
+use crate::protection::{HashAttributes, SHEET_HASH, WORKBOOK_HASH};
+use crate::rows::restore_table_cells;
+use crate::sql::from_sql;
+use rust_xlsxwriter::{
+    Chart as XlsxChart, ChartType, Color, ConditionalFormat2ColorScale,
+    ConditionalFormat3ColorScale, ConditionalFormatAverage, ConditionalFormatAverageRule,
+    ConditionalFormatBlank, ConditionalFormatCell, ConditionalFormatCellRule,
+    ConditionalFormatCustomIcon, ConditionalFormatDataBar, ConditionalFormatDate,
+    ConditionalFormatDateRule, ConditionalFormatDuplicate, ConditionalFormatError,
+    ConditionalFormatFormula, ConditionalFormatIconSet, ConditionalFormatIconType,
+    ConditionalFormatText, ConditionalFormatTextRule, ConditionalFormatTop,
+    ConditionalFormatTopRule, ConditionalFormatType, DataValidation as XlsxDataValidation,
+    DataValidationErrorStyle, DataValidationRule, Format, FormatAlign, FormatBorder,
+    FormatDiagonalBorder, FormatPattern, FormatScript, FormatUnderline, Formula, Image, Note,
+    ProtectionOptions, Table as XlsxTable, TableColumn, TableStyle, Url, Workbook as XlsxWorkbook,
+    Worksheet, XlsxError,
+};
+use std::collections::{HashMap, HashSet};
+
+pub fn parse_document(bytes: &[u8], format: &str) -> Result<Workbook, AppError> {
+    let invalid = |message: String| AppError::InvalidDocument {
+        format: format.to_string(),
+        message,
+        location: None,
+    };
+
+    let text = std::str::from_utf8(bytes)
+        .map_err(|e| invalid(format!("Input is not valid UTF-8: {}", e)))?;
+    let wb: Workbook = match format {
+        "json" => from_value(serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?)
+            .map_err(|e| invalid(e.to_string()))?,
+        "yaml" => from_value(serde_yaml::from_str(text).map_err(|e| invalid(e.to_string()))?)
+            .map_err(|e| invalid(e.to_string()))?,
+        "xml" => serde_xml_rs::SerdeXml::new()
+            .parser(
+                xml::ParserConfig::new()
+                    .trim_whitespace(false)
+                    .whitespace_to_characters(true)
+                    .cdata_to_characters(true)
+                    .ignore_comments(true)
+                    .coalesce_characters(true),
+            )
+            .from_str(text)
+            .map_err(|e| invalid(e.to_string()))?,
+        "sql" => from_sql(text).map_err(invalid)?,
+        _ => {
+            return Err(AppError::UnsupportedFormat {
+                field: "format",
+                value: format.to_string(),
+            });
+        }
+    };
+
+    if let Some(cell) = wb
+        .cells
+        .iter()
+        .find(|c| c.row == 0 || c.col == 0 || c.col > u16::MAX as u32)
+    {
+        return Err(AppError::InvalidDocument {
+            format: format.to_string(),
+            message: format!("Invalid cell position: row {}, col {}", cell.row, cell.col),
+            location: Some(ErrorLocation {
+                sheet: cell.sheet.clone(),
+                cell: Some(cell.address.clone()),
+            }),
+        });
+    }
+
+    if let Some(drawing) = wb.drawings.iter().find(|d| {
+        d.data
+            .as_deref()
+            .is_some_and(|data| base64_decode(data).is_none())
+    }) {
+        return Err(AppError::InvalidDocument {
+            format: format.to_string(),
+            message: "Drawing data is not valid base64".to_string(),
+            location: Some(ErrorLocation {
+                sheet: drawing.sheet.clone(),
+                cell: drawing
+                    .range
+                    .as_deref()
+                    .and_then(|r| r.split(':').next())
+                    .map(ToString::to_string),
+            }),
+        });
+    }
+
+    Ok(wb)
+}
+
+/// typed モードの出力（`{"type": ..., "value": ...}`）も受け付けるよう、
+/// セルの `value` を `{"value": "文字列"}` に揃えてから読み込む（`model::plain_or_typed_value`）。
+/// rows モードの出力は `table_rows` からテーブル内のセルを作り直す
+fn from_value(mut doc: serde_json::Value) -> Result<Workbook, serde_json::Error> {
+    let cells = doc.get_mut("cells").and_then(|c| c.as_array_mut());
+    for cell in cells.into_iter().flatten() {
+        if let Some(value) = cell.get_mut("value") {
+            let inner = match &mut *value {
+                serde_json::Value::Object(tagged) => {
+                    tagged.remove("value").unwrap_or(serde_json::Value::Null)
+                }
+                other => std::mem::take(other),
+            };
+            let text = match inner {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            *value = serde_json::json!({ "value": text });
+        }
+    }
+    // rows モードの出力では、テーブル内のセルの一部が `table_rows` にだけある
+    let table_rows = doc.as_object_mut().and_then(|d| d.remove("table_rows"));
+    let mut wb: Workbook = serde_json::from_value(doc)?;
+    if let Some(table_rows) = table_rows {
+        restore_table_cells(&mut wb, serde_json::from_value(table_rows)?);
+    }
+    Ok(wb)
+}
+
+/// ピボットテーブルは rust_xlsxwriter が作成できないため、出力範囲のセルは値として書き込む
+pub fn restore_xlsx(wb: &Workbook) -> Result<Vec<u8>, AppError> {
+    let mut xlsx = XlsxWorkbook::new();
+
+    let mut sheets: Vec<_> = wb.sheets.iter().collect();
+    sheets.sort_by_key(|s| s.index);
+    let active = sheets
+        .iter()
+        .position(|s| visibility(s) == SheetVisibility::Visible)
+        .unwrap_or(0);
+
+    for (pos, sheet) in sheets.iter().enumerate() {
+        let worksheet = xlsx.add_worksheet();
+        write_sheet(worksheet, sheet, wb, pos == active)
+            .map_err(|e| write_error(e, Some(&sheet.name)))?;
+    }
+
+    for name in &wb.defined_names {
+        define_name(&mut xlsx, name)?;
+    }
+
+    let bytes = xlsx.save_to_buffer().map_err(|e| write_error(e, None))?;
+    patch_package(bytes, wb)
+}
+
+/// rust_xlsxwriter が書けない内容を、保存後のパッケージの部品を差し替えて補う。
+/// 差し替える部品をまとめてから 1 度だけ書き直す
+fn patch_package(bytes: Vec<u8>, wb: &Workbook) -> Result<Vec<u8>, AppError> {
+    let parts = {
+        let Some(mut package) = Package::open(&bytes) else {
+            return Ok(bytes);
+        };
+        let mut parts = note_author_parts(&mut package, wb);
+        parts.extend(phonetic_parts(&mut package, wb)?);
+        parts.extend(protection_parts(&mut package, wb));
+        error_value_parts(&mut package, wb, &mut parts)
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+        hidden_name_parts(&mut package, wb, &mut parts);
+        parts
+    };
+    if parts.is_empty() {
+        return Ok(bytes);
+    }
+    ooxml::replace_parts(&bytes, &parts).map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+/// 印刷範囲などの組み込み名（`_xlnm.`）は rust_xlsxwriter が各機能から生成するので復元しない。
+/// 非表示フラグは rust_xlsxwriter が対応していないため、保存後に書き込む（`hidden_name_parts`）
+fn define_name(xlsx: &mut XlsxWorkbook, name: &DefinedName) -> Result<(), AppError> {
+    if name.name.starts_with("_xlnm.") {
+        return Ok(());
+    }
+    let error = |message: String| AppError::Restore {
+        sheet: name.scope.clone(),
+        message,
+    };
+    // 空の名前は rust_xlsxwriter 内で panic するので先に弾く
+    if name.name.is_empty() {
+        return Err(error("Defined name must not be empty".into()));
+    }
+    let qualified = match &name.scope {
+        Some(sheet) => format!("'{}'!{}", sheet.replace('\'', "''"), name.name),
+        None => name.name.clone(),
+    };
+    xlsx.define_name(qualified, &format!("={}", name.refers_to))
+        .map_err(|e| error(e.to_string()))?;
+    Ok(())
+}
+
+/// rust_xlsxwriter はメモの作成者一覧を名前順で書き出す一方、`authorId` は登場順に
+/// 振るため、作成者が複数いると取り違える。復元元のコメントから id 順の一覧に書き直す
+fn note_author_parts(package: &mut Package, wb: &Workbook) -> Vec<(String, Vec<u8>)> {
+    let mut parts = Vec::new();
+    if wb.comments.is_empty() {
+        return parts;
+    }
+    for sheet in &wb.sheets {
+        let Some(path) = package.sheet_path(&sheet.name).map(ToString::to_string) else {
+            continue;
+        };
+        let Some(target) = package
+            .relationships(&path)
+            .into_iter()
+            .find(|r| r.is("comments"))
+            .map(|r| r.target)
+        else {
+            continue;
+        };
+        let (Some(part), Some(raw)) = (package.part(&target), package.raw_part(&target)) else {
+            continue;
+        };
+
+        let mut authors: Vec<String> = part
+            .child("authors")
+            .into_iter()
+            .flat_map(|a| a.children_named("author"))
+            .map(|a| a.text.clone())
+            .collect();
+        let original = authors.clone();
+        for comment in part
+            .child("commentList")
+            .into_iter()
+            .flat_map(|l| l.children_named("comment"))
+        {
+            let (Some(address), Some(id)) = (
+                comment.attr("ref"),
+                comment
+                    .attr("authorId")
+                    .and_then(|i| i.parse::<usize>().ok()),
+            ) else {
+                continue;
+            };
+            let author = wb
+                .comments
+                .iter()
+                .find(|c| c.sheet == sheet.name && c.address == address)
+                .map(|c| c.author.as_deref().unwrap_or("Author"));
+            if let (Some(author), Some(slot)) = (author, authors.get_mut(id)) {
+                *slot = author.to_string();
+            }
+        }
+        if authors == original {
+            continue;
+        }
+
+        let xml = String::from_utf8_lossy(&raw);
+        let (Some(start), Some(end)) = (xml.find("<authors>"), xml.find("</authors>")) else {
+            continue;
+        };
+        let list: String = authors
+            .iter()
+            .map(|a| format!("<author>{}</author>", escape_xml(a)))
+            .collect();
+        let fixed = format!("{}<authors>{}{}", &xml[..start], list, &xml[end..]);
+        parts.push((target, fixed.into_bytes()));
+    }
+    parts
+}
+
+/// rust_xlsxwriter はふりがなを書けないため、保存後の共有文字列の `<si>` に `<rPh>` を加える。
+/// 同じ本文の文字列は 1 つの `<si>` にまとめられるので、読みの異なるセルがあればエラーにする
+fn phonetic_parts(
+    package: &mut Package,
+    wb: &Workbook,
+) -> Result<Vec<(String, Vec<u8>)>, AppError> {
+    if wb.cells.iter().all(|c| c.phonetic.is_empty()) {
+        return Ok(Vec::new());
+    }
+
+    let mut by_index: HashMap<usize, &CellData> = HashMap::new();
+    for sheet in &wb.sheets {
+        let Some((_, xml)) = package.sheet(&sheet.name) else {
+            continue;
+        };
+        let indices: HashMap<&str, usize> = xml
+            .child("sheetData")
+            .into_iter()
+            .flat_map(|d| d.children_named("row"))
+            .flat_map(|r| r.children_named("c"))
+            .filter(|c| c.attr("t") == Some("s"))
+            .filter_map(|c| Some((c.attr("r")?, c.child("v")?.text.trim().parse().ok()?)))
+            .collect();
+        for cell in wb.cells.iter().filter(|c| c.sheet == sheet.name) {
+            let Some(&idx) = indices.get(cell.address.as_str()) else {
+                continue;
+            };
+            match by_index.get(&idx) {
+                None => {
+                    by_index.insert(idx, cell);
+                }
+                Some(first) if first.phonetic != cell.phonetic => {
+                    return Err(AppError::Restore {
+                        sheet: Some(sheet.name.clone()),
+                        message: format!(
+                            "Cell {} has the same text as {}!{} but different phonetic runs",
+                            cell.address, first.sheet, first.address
+                        ),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    by_index.retain(|_, cell| !cell.phonetic.is_empty());
+
+    let path = "xl/sharedStrings.xml";
+    let Some(raw) = package.raw_part(path) else {
+        return Ok(Vec::new());
+    };
+    let fixed = insert_phonetic_runs(&raw, &by_index)
+        .map_err(|e| AppError::Serialization(e.to_string()))?;
+    Ok(vec![(path.to_string(), fixed)])
+}
+
+/// `<sst>` を読み直しながら、`idx` 番目の `<si>` の末尾（本文と書式付き区間の後ろ）に `<rPh>` を書く
+fn insert_phonetic_runs(
+    xml: &[u8],
+    cells: &HashMap<usize, &CellData>,
+) -> Result<Vec<u8>, xml::writer::Error> {
+    use xml::writer::XmlEvent as Write;
+
+    let reader = xml::ParserConfig::new()
+        .trim_whitespace(false)
+        .whitespace_to_characters(true)
+        .create_reader(xml);
+    let mut writer = xml::EmitterConfig::new()
+        .perform_indent(false)
+        .create_writer(Vec::new());
+    let (mut depth, mut idx) = (0, 0);
+    for event in reader {
+        let event = event.map_err(|e| xml::writer::Error::Io(std::io::Error::other(e)))?;
+        match &event {
+            xml::reader::XmlEvent::StartElement { .. } => depth += 1,
+            xml::reader::XmlEvent::EndElement { name } => {
+                depth -= 1;
+                if depth == 1 && name.local_name == "si" {
+                    for run in cells.get(&idx).map_or(&[][..], |c| &c.phonetic) {
+                        let (start, end) = (run.start.to_string(), run.end.to_string());
+                        writer.write(
+                            Write::start_element("rPh")
+                                .attr("sb", &start)
+                                .attr("eb", &end),
+                        )?;
+                        writer.write(Write::start_element("t"))?;
+                        writer.write(Write::characters(&run.text))?;
+                        writer.write(Write::end_element())?;
+                        writer.write(Write::end_element())?;
+                    }
+                    idx += 1;
+                }
+            }
+            _ => {}
+        }
+        if let Some(event) = event.as_writer_event() {
+            writer.write(event)?;
+        }
+    }
+    Ok(writer.into_inner())
+}
+
+/// 非表示の名前の `definedName` に `hidden="1"` を加える。rust_xlsxwriter は
+/// `name`、`localSheetId`（シートスコープのみ）の順に属性を書き、シートは `index` 順に並べる
+fn hidden_name_parts(package: &mut Package, wb: &Workbook, parts: &mut Vec<(String, Vec<u8>)>) {
+    let mut sheets: Vec<_> = wb.sheets.iter().collect();
+    sheets.sort_by_key(|s| s.index);
+    let tags: Vec<String> = wb
+        .defined_names
+        .iter()
+        .filter(|n| n.hidden && !n.name.starts_with("_xlnm."))
+        .filter_map(|n| {
+            let local = match &n.scope {
+                Some(scope) => {
+                    let id = sheets.iter().position(|s| s.name == *scope)?;
+                    format!(" localSheetId=\"{}\"", id)
+                }
+                None => String::new(),
+            };
+            Some(format!(
+                "<definedName name=\"{}\"{}",
+                escape_xml(&n.name),
+                local
+            ))
+        })
+        .collect();
+    if tags.is_empty() {
+        return;
+    }
+    let Ok(()) = rewrite_part(package, parts, "xl/workbook.xml", |xml| {
+        let mut xml = String::from_utf8_lossy(&xml).into_owned();
+        for tag in &tags {
+            xml = xml.replacen(&format!("{}>", tag), &format!("{} hidden=\"1\">", tag), 1);
+        }
+        Ok::<_, std::convert::Infallible>(xml.into_bytes())
+    });
+}
+
+/// 差し替え済みの部品ならその内容を、そうでなければ元の部品を `rewrite` で書き直す
+fn rewrite_part<E>(
+    package: &mut Package,
+    parts: &mut Vec<(String, Vec<u8>)>,
+    path: &str,
+    rewrite: impl FnOnce(Vec<u8>) -> Result<Vec<u8>, E>,
+) -> Result<(), E> {
+    match parts.iter_mut().find(|(p, _)| p == path) {
+        Some((_, xml)) => *xml = rewrite(std::mem::take(xml))?,
+        None => {
+            if let Some(raw) = package.raw_part(path) {
+                parts.push((path.to_string(), rewrite(raw)?));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 数式の無いエラーセルを値だけのセル（`t="e"` と `<v>`）にする。
+/// シートの部品は保護の書き込みでも差し替えるので、`rewrite_part` で重ねて書き直す
+fn error_value_parts(
+    package: &mut Package,
+    wb: &Workbook,
+    parts: &mut Vec<(String, Vec<u8>)>,
+) -> Result<(), xml::writer::Error> {
+    for sheet in &wb.sheets {
+        let addresses: HashSet<String> = wb
+            .cells
+            .iter()
+            .filter(|c| c.sheet == sheet.name && c.formula.is_none())
+            .filter(|c| c.data_type == CellType::Error && error_literal(&c.value).is_some())
+            .map(|c| format!("{}{}", col_to_letter(c.col), c.row))
+            .collect();
+        if addresses.is_empty() {
+            continue;
+        }
+        let Some(path) = package.sheet_path(&sheet.name).map(ToString::to_string) else {
+            continue;
+        };
+        rewrite_part(package, parts, &path, |xml| {
+            remove_formulas(&xml, &addresses)
+        })?;
+    }
+    Ok(())
+}
+
+/// `addresses` のセルの `<f>` を読み飛ばしてシートを書き直す
+fn remove_formulas(xml: &[u8], addresses: &HashSet<String>) -> Result<Vec<u8>, xml::writer::Error> {
+    let reader = xml::ParserConfig::new()
+        .trim_whitespace(false)
+        .whitespace_to_characters(true)
+        .create_reader(xml);
+    let mut writer = xml::EmitterConfig::new()
+        .perform_indent(false)
+        .create_writer(Vec::new());
+    let (mut in_target, mut skipping) = (false, 0);
+    for event in reader {
+        let event = event.map_err(|e| xml::writer::Error::Io(std::io::Error::other(e)))?;
+        match &event {
+            xml::reader::XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                if skipping > 0 || (in_target && name.local_name == "f") {
+                    skipping += 1;
+                    continue;
+                }
+                if name.local_name == "c" {
+                    in_target = attributes
+                        .iter()
+                        .any(|a| a.name.local_name == "r" && addresses.contains(&a.value));
+                }
+            }
+            xml::reader::XmlEvent::EndElement { name } => {
+                if skipping > 0 {
+                    skipping -= 1;
+                    continue;
+                }
+                if name.local_name == "c" {
+                    in_target = false;
+                }
+            }
+            _ if skipping > 0 => continue,
+            _ => {}
+        }
+        if let Some(event) = event.as_writer_event() {
+            writer.write(event)?;
+        }
+    }
+    Ok(writer.into_inner())
+}
+
+/// rust_xlsxwriter はブックの保護に対応しておらず、シートのパスワードも平文からしか設定できない。
+/// 保存後の部品に `workbookProtection` とハッシュの属性を書き込む。
+/// ハッシュを含まない（`has_password` だけの）文書からはパスワード無しの保護として復元する
+/// （件数は `X-Dropped-Password-Protection` で返す）
+fn protection_parts(package: &mut Package, wb: &Workbook) -> Vec<(String, Vec<u8>)> {
+    let sheet_passwords = wb
+        .sheets
+        .iter()
+        .filter_map(|s| Some((s.name.as_str(), s.protection.as_ref()?.password.as_ref()?)));
+
+    let mut parts = Vec::new();
+    for (name, password) in sheet_passwords {
+        let Some(path) = package.sheet_path(name).map(ToString::to_string) else {
+            continue;
+        };
+        let Some(raw) = package.raw_part(&path) else {
+            continue;
+        };
+        let xml = String::from_utf8_lossy(&raw);
+        let Some(pos) = xml.find("<sheetProtection ") else {
+            continue;
+        };
+        let pos = pos + "<sheetProtection ".len();
+        let fixed = format!(
+            "{}{} {}",
+            &xml[..pos],
+            hash_attributes(password, &SHEET_HASH),
+            &xml[pos..]
+        );
+        parts.push((path, fixed.into_bytes()));
+    }
+
+    let path = "xl/workbook.xml";
+    if let Some(protection) = &wb.protection
+        && let Some(raw) = package.raw_part(path)
+    {
+        let xml = String::from_utf8_lossy(&raw);
+        // `workbookProtection` は `bookViews` の直前に置く
+        if let Some(pos) = xml.find("<bookViews") {
+            let mut attributes: Vec<String> = protection
+                .password
+                .iter()
+                .map(|p| hash_attributes(p, &WORKBOOK_HASH))
+                .collect();
+            if protection.structure {
+                attributes.push("lockStructure=\"1\"".to_string());
+            }
+            if protection.windows {
+                attributes.push("lockWindows=\"1\"".to_string());
+            }
+            let fixed = format!(
+                "{}<workbookProtection {}/>{}",
+                &xml[..pos],
+                attributes.join(" "),
+                &xml[pos..]
+            );
+            parts.push((path.to_string(), fixed.into_bytes()));
+        }
+    }
+    parts
+}
+
+/// ハッシュを属性の並びにする。アルゴリズムが無ければ旧形式の属性に書く
+fn hash_attributes(password: &PasswordHash, names: &HashAttributes) -> String {
+    let [
+        algorithm_name,
+        hash_name,
+        salt_name,
+        spin_count_name,
+        legacy_name,
+    ] = names;
+    let Some(algorithm) = &password.algorithm else {
+        return format!("{}=\"{}\"", legacy_name, escape_xml(&password.hash));
+    };
+    let mut attributes = vec![
+        format!("{}=\"{}\"", algorithm_name, escape_xml(algorithm)),
+        format!("{}=\"{}\"", hash_name, escape_xml(&password.hash)),
+    ];
+    if let Some(salt) = &password.salt {
+        attributes.push(format!("{}=\"{}\"", salt_name, escape_xml(salt)));
+    }
+    if let Some(spin_count) = password.spin_count {
+        attributes.push(format!("{}=\"{}\"", spin_count_name, spin_count));
+    }
+    attributes.join(" ")
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// I/O 起因以外の書き出しエラーは入力内容の問題として扱う
+fn write_error(e: XlsxError, sheet: Option<&str>) -> AppError {
+    match e {
+        XlsxError::IoError(_) | XlsxError::ZipError(_) => AppError::Serialization(e.to_string()),
+        _ => AppError::Restore {
+            sheet: sheet.map(ToString::to_string),
+            message: e.to_string(),
+        },
+    }
+}
+
+fn write_sheet(
+    worksheet: &mut Worksheet,
+    sheet: &SheetMetadata,
+    wb: &Workbook,
+    active: bool,
+) -> Result<(), XlsxError> {
+    worksheet.set_name(&sheet.name)?;
+    if active {
+        worksheet.set_active(true);
+    } else {
+        match visibility(sheet) {
+            SheetVisibility::Visible => {}
+            SheetVisibility::Hidden => {
+                worksheet.set_hidden(true);
+            }
+            SheetVisibility::VeryHidden => {
+                worksheet.set_very_hidden(true);
+            }
+        }
+    }
+
+    apply_layout(worksheet, sheet)?;
+    if let Some(setup) = &sheet.print_setup {
+        apply_print_setup(worksheet, setup)?;
+    }
+    if let Some(protection) = &sheet.protection {
+        worksheet.protect_with_options(&protection_options(protection));
+    }
+
+    for merged in wb.merged_ranges.iter().filter(|m| m.sheet == sheet.name) {
+        let (Some(start), Some(end)) = (parse_address(&merged.start), parse_address(&merged.end))
+        else {
+            continue;
+        };
+        if start == end {
+            continue;
+        }
+        worksheet.merge_range(
+            start.0,
+            start.1 as u16,
+            end.0,
+            end.1 as u16,
+            "",
+            &Format::new(),
+        )?;
+    }
+
+    // テーブルも見出し文字列を書き込むので、セルより先に追加する
+    for table in wb.tables.iter().filter(|t| t.sheet == sheet.name) {
+        add_table(worksheet, table)?;
+    }
+
+    // rust_xlsxwriter はリンクと一緒に文字列セルを書くため、先にリンクを置いてから
+    // セルの値で上書きする。範囲リンクは左上のセルにのみ設定する
+    for link in wb.hyperlinks.iter().filter(|l| l.sheet == sheet.name) {
+        let Some((row, col)) = link.range.split(':').next().and_then(parse_address) else {
+            continue;
+        };
+        let mut url = match link.internal {
+            true => Url::new(format!("internal:{}", link.target)),
+            false => Url::new(&link.target),
+        };
+        if let Some(tooltip) = &link.tooltip {
+            url = url.set_tip(tooltip);
+        }
+        // 表示文字列はセル自身の値にする。値が無ければ URL が書かれるので空白で上書きする
+        let text = wb
+            .cells
+            .iter()
+            .find(|c| c.sheet == sheet.name && (c.row - 1, c.col - 1) == (row, col))
+            .map(|c| c.value.as_str())
+            .filter(|text| !text.is_empty());
+        match worksheet.write_url_with_text(row, col as u16, url, text.unwrap_or_default()) {
+            // rust_xlsxwriter が扱えない種類の URL は復元しない
+            Err(XlsxError::UnknownUrlType(_)) => continue,
+            result => {
+                result?;
+            }
+        }
+        if text.is_none() {
+            worksheet.write_blank(row, col as u16, &Format::new().set_hyperlink())?;
+        }
+    }
+
+    let date1904 = wb.date1904.unwrap_or(false);
+    for cell in wb.cells.iter().filter(|c| c.sheet == sheet.name) {
+        let (row, col) = (cell.row - 1, (cell.col - 1) as u16);
+        let format = cell_format(cell, &wb.styles);
+
+        if let Some(formula) = &cell.formula {
+            let formula = Formula::new(formula).set_result(formula_result(cell, date1904));
+            worksheet.write_formula(row, col, formula)?;
+        } else {
+            match cell.data_type {
+                CellType::Empty => match &format {
+                    // 書式だけを持つ空セル
+                    Some(format) => worksheet.write_blank(row, col, format)?,
+                    None => continue,
+                },
+                CellType::Number => match cell.value.parse::<f64>() {
+                    Ok(n) => worksheet.write_number(row, col, n)?,
+                    Err(_) => worksheet.write_string(row, col, &cell.value)?,
+                },
+                CellType::Boolean => match cell.value.parse::<bool>() {
+                    Ok(b) => worksheet.write_boolean(row, col, b)?,
+                    Err(_) => worksheet.write_string(row, col, &cell.value)?,
+                },
+                CellType::DateTime | CellType::Duration => match date_serial(cell, date1904) {
+                    Some(n) => worksheet.write_number(row, col, n)?,
+                    None => worksheet.write_string(row, col, &cell.value)?,
+                },
+                // エラー値を直接書く API が無いので、キャッシュ値がエラーの数式として書き、
+                // 保存後に数式を取り除く（`error_value_parts`）
+                CellType::Error => match error_literal(&cell.value) {
+                    Some(e) => worksheet.write_formula(
+                        row,
+                        col,
+                        Formula::new(format!("={}", e)).set_result(e),
+                    )?,
+                    None => worksheet.write_string(row, col, &cell.value)?,
+                },
+                CellType::String if !cell.runs.is_empty() => write_runs(worksheet, cell)?,
+                _ => worksheet.write_string(row, col, &cell.value)?,
+            };
+        }
+
+        if let Some(format) = format {
+            worksheet.set_cell_format(row, col, &format)?;
+        }
+    }
+
+    for validation in wb.data_validations.iter().filter(|v| v.sheet == sheet.name) {
+        add_data_validation(worksheet, validation)?;
+    }
+
+    for rule in wb
+        .conditional_formats
+        .iter()
+        .filter(|c| c.sheet == sheet.name)
+    {
+        add_conditional_format(worksheet, rule, &wb.differential_styles)?;
+    }
+
+    // rust_xlsxwriter はスレッド形式のコメントを書けないため、先頭のコメントのみメモとして復元する
+    for comment in wb.comments.iter().filter(|c| c.sheet == sheet.name) {
+        let Some((row, col)) = parse_address(&comment.address) else {
+            continue;
+        };
+        let mut note = Note::new(&comment.text).add_author_prefix(false);
+        if let Some(author) = &comment.author {
+            note = note.set_author(author);
+        }
+        worksheet.insert_note(row, col as u16, &note)?;
+    }
+
+    // 図形は rust_xlsxwriter で元の形を再現できないため、中身を含む画像のみ復元する。
+    // グラフは描画ではなく定義（`charts`）から作り直す
+    for drawing in wb
+        .drawings
+        .iter()
+        .filter(|d| d.sheet == sheet.name && d.kind == DrawingKind::Image)
+    {
+        insert_image(worksheet, drawing)?;
+    }
+
+    for chart in wb.charts.iter().filter(|c| c.sheet == sheet.name) {
+        insert_chart(worksheet, chart)?;
+    }
+
+    Ok(())
+}
+
+/// グラフを種類・タイトル・系列の参照範囲から作り直す。書式や軸の設定は既定のままになる。
+/// セルに固定されていないグラフ、rust_xlsxwriter に無い種類、値の範囲を持たない系列は復元しない
+fn insert_chart(worksheet: &mut Worksheet, chart: &Chart) -> Result<(), XlsxError> {
+    let Some((row, col)) = chart
+        .range
+        .as_deref()
+        .and_then(|r| r.split(':').next())
+        .and_then(parse_address)
+    else {
+        return Ok(());
+    };
+    let Some(chart_type) = chart_type(chart) else {
+        return Ok(());
+    };
+    // 複合グラフの別の種類のプロットに属する系列は再現できない
+    let series: Vec<_> = chart
+        .series
+        .iter()
+        .filter(|s| s.chart_type.is_none() && s.values.is_some())
+        .collect();
+    if series.is_empty() {
+        return Ok(());
+    }
+    let mut xlsx_chart = XlsxChart::new(chart_type);
+    for series in series {
+        let xlsx_series = xlsx_chart.add_series();
+        if let Some(values) = &series.values {
+            xlsx_series.set_values(format!("={}", values).as_str());
+        }
+        if let Some(categories) = &series.categories {
+            xlsx_series.set_categories(format!("={}", categories).as_str());
+        }
+        match (&series.name_ref, &series.name) {
+            (Some(name_ref), _) => {
+                xlsx_series.set_name(format!("={}", name_ref).as_str());
+            }
+            (None, Some(name)) => {
+                xlsx_series.set_name(name);
+            }
+            (None, None) => {}
+        }
+    }
+    if let Some(title) = &chart.title {
+        xlsx_chart.title().set_name(title);
+    }
+    if let Some(name) = &chart.name {
+        xlsx_chart.set_name(name);
+    }
+    if let Some(width) = chart.width {
+        xlsx_chart.set_width(width);
+    }
+    if let Some(height) = chart.height {
+        xlsx_chart.set_height(height);
+    }
+    match worksheet.insert_chart_with_offset(
+        row,
+        col as u16,
+        &xlsx_chart,
+        chart.x_offset,
+        chart.y_offset,
+    ) {
+        // 参照範囲の式を解釈できないグラフは復元しない
+        Err(XlsxError::ChartError(_)) => Ok(()),
+        result => result.map(|_| ()),
+    }
+}
+
+/// OOXML のプロットの種類・向き・積み上げ方を rust_xlsxwriter の種類に対応付ける
+fn chart_type(chart: &Chart) -> Option<ChartType> {
+    let grouping = chart.grouping.as_deref();
+    let stacked = |plain, stacked, percent| match grouping {
+        Some("stacked") => stacked,
+        Some("percentStacked") => percent,
+        _ => plain,
+    };
+    Some(match chart.chart_type.as_str() {
+        "bar" | "bar3D" if chart.bar_direction.as_deref() == Some("bar") => stacked(
+            ChartType::Bar,
+            ChartType::BarStacked,
+            ChartType::BarPercentStacked,
+        ),
+        "bar" | "bar3D" => stacked(
+            ChartType::Column,
+            ChartType::ColumnStacked,
+            ChartType::ColumnPercentStacked,
+        ),
+        "line" | "line3D" => stacked(
+            ChartType::Line,
+            ChartType::LineStacked,
+            ChartType::LinePercentStacked,
+        ),
+        "area" | "area3D" => stacked(
+            ChartType::Area,
+            ChartType::AreaStacked,
+            ChartType::AreaPercentStacked,
+        ),
+        "pie" | "pie3D" | "ofPie" => ChartType::Pie,
+        "doughnut" => ChartType::Doughnut,
+        "scatter" => match chart.style.as_deref() {
+            Some("line") => ChartType::ScatterStraight,
+            Some("lineMarker") => ChartType::ScatterStraightWithMarkers,
+            Some("smooth") => ChartType::ScatterSmooth,
+            Some("smoothMarker") => ChartType::ScatterSmoothWithMarkers,
+            // `marker` と、省略・未知の値はマーカーのみ
+            _ => ChartType::Scatter,
+        },
+        "radar" => match chart.style.as_deref() {
+            Some("marker") => ChartType::RadarWithMarkers,
+            Some("filled") => ChartType::RadarFilled,
+            _ => ChartType::Radar,
+        },
+        "stock" => ChartType::Stock,
+        _ => return None,
+    })
+}
+
+/// 画像を左上のセルとずれの位置に、元の表示サイズで配置する。
+/// セルに固定されていない画像と、rust_xlsxwriter が扱えない形式の画像は復元しない
+fn insert_image(worksheet: &mut Worksheet, drawing: &Drawing) -> Result<(), XlsxError> {
+    let Some(bytes) = drawing.data.as_deref().and_then(base64_decode) else {
+        return Ok(());
+    };
+    let Some((row, col)) = drawing
+        .range
+        .as_deref()
+        .and_then(|r| r.split(':').next())
+        .and_then(parse_address)
+    else {
+        return Ok(());
+    };
+    let mut image = match Image::new_from_buffer(&bytes) {
+        Err(XlsxError::UnknownImageType | XlsxError::ImageDimensionError) => return Ok(()),
+        result => result?,
+    };
+    if let Some(width) = drawing.width {
+        image = image.set_width(width);
+    }
+    if let Some(height) = drawing.height {
+        image = image.set_height(height);
+    }
+    if let Some(alt_text) = &drawing.alt_text {
+        image = image.set_alt_text(alt_text);
+    }
+    worksheet.insert_image_with_offset(
+        row,
+        col as u16,
+        &image,
+        drawing.x_offset,
+        drawing.y_offset,
+    )?;
+    Ok(())
+}
+
+/// 書式付き文字列を書き込む。rust_xlsxwriter は空の区間を受け付けないので除く。
+/// 区間を連結しても `value` にならない（`value` だけが編集された）場合は `value` を優先する
+fn write_runs<'w>(
+    worksheet: &'w mut Worksheet,
+    cell: &CellData,
+) -> Result<&'w mut Worksheet, XlsxError> {
+    let (row, col) = (cell.row - 1, (cell.col - 1) as u16);
+    if cell
+        .runs
+        .iter()
+        .map(|r| r.text.as_str())
+        .collect::<String>()
+        != cell.value
+    {
+        return worksheet.write_string(row, col, &cell.value);
+    }
+    let runs: Vec<(Format, &str)> = cell
+        .runs
+        .iter()
+        .filter(|r| !r.text.is_empty())
+        .map(|r| {
+            let format = match &r.font {
+                Some(font) => apply_style(
+                    Format::new(),
+                    &CellStyle {
+                        font: Some(font.clone()),
+                        ..Default::default()
+                    },
+                ),
+                None => Format::default(),
+            };
+            (format, r.text.as_str())
+        })
+        .collect();
+    if runs.is_empty() {
+        return worksheet.write_string(row, col, &cell.value);
+    }
+    let segments: Vec<(&Format, &str)> = runs.iter().map(|(f, t)| (f, *t)).collect();
+    worksheet.write_rich_string(row, col, &segments)
+}
+
+/// 列幅・行の高さ・ウィンドウ枠の固定などを設定する。既定の列幅は rust_xlsxwriter で
+/// 変更できないため復元しない
+fn apply_layout(worksheet: &mut Worksheet, sheet: &SheetMetadata) -> Result<(), XlsxError> {
+    if let Some(c) = sheet.tab_color.as_deref().and_then(color) {
+        worksheet.set_tab_color(c);
+    }
+    if let Some(zoom) = sheet.zoom {
+        worksheet.set_zoom(zoom);
+    }
+    if sheet.hide_gridlines {
+        worksheet.set_screen_gridlines(false);
+    }
+    if let Some(height) = sheet.default_row_height {
+        worksheet.set_default_row_height(height);
+    }
+    if let Some(freeze) = &sheet.freeze_panes {
+        worksheet.set_freeze_panes(freeze.rows, freeze.cols as u16)?;
+        if let Some((row, col)) = freeze.top_left_cell.as_deref().and_then(parse_address) {
+            worksheet.set_freeze_panes_top_cell(row, col as u16)?;
+        }
+    }
+
+    // 折りたたみの印は直後の行・列に付くため、グループ最後の行・列を折りたたみ付きで設定する
+    let collapsed_cols: Vec<u32> = sheet
+        .columns
+        .iter()
+        .filter(|c| c.collapsed)
+        .map(|c| c.first_col)
+        .collect();
+    for layout in &sheet.columns {
+        // `max` はシート末尾（16384 列目）までを指すことがある
+        for col in layout.first_col.max(1)..=layout.last_col.min(16_384) {
+            let col = (col - 1) as u16;
+            if let Some(width) = layout.width {
+                // OOXML の列幅は 7 ピクセル単位の文字幅
+                worksheet.set_column_width_pixels(col, (width * 7.0).round() as u32)?;
+            }
+            if layout.hidden {
+                worksheet.set_column_hidden(col)?;
+            }
+            for level in 1..=layout.outline_level {
+                let last = level == layout.outline_level;
+                if last && layout.hidden && collapsed_cols.contains(&(u32::from(col) + 2)) {
+                    worksheet.group_columns_collapsed(col, col)?;
+                } else {
+                    worksheet.group_columns(col, col)?;
+                }
+            }
+        }
+    }
+
+    let collapsed_rows: Vec<u32> = sheet
+        .rows
+        .iter()
+        .filter(|r| r.collapsed)
+        .map(|r| r.row)
+        .collect();
+    for layout in &sheet.rows {
+        let Some(row) = layout.row.checked_sub(1) else {
+            continue;
+        };
+        if let Some(height) = layout.height {
+            worksheet.set_row_height(row, height)?;
+        }
+        if layout.hidden {
+            worksheet.set_row_hidden(row)?;
+        }
+        for level in 1..=layout.outline_level {
+            let last = level == layout.outline_level;
+            if last && layout.hidden && collapsed_rows.contains(&(layout.row + 1)) {
+                worksheet.group_rows_collapsed(row, row)?;
+            } else {
+                worksheet.group_rows(row, row)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 保護中に許可する操作。パスワードのハッシュは保存後に `add_protection` で書き込む
+fn protection_options(protection: &SheetProtection) -> ProtectionOptions {
+    ProtectionOptions {
+        select_locked_cells: protection.select_locked_cells,
+        select_unlocked_cells: protection.select_unlocked_cells,
+        format_cells: protection.format_cells,
+        format_columns: protection.format_columns,
+        format_rows: protection.format_rows,
+        insert_columns: protection.insert_columns,
+        insert_rows: protection.insert_rows,
+        insert_links: protection.insert_hyperlinks,
+        delete_columns: protection.delete_columns,
+        delete_rows: protection.delete_rows,
+        sort: protection.sort,
+        use_autofilter: protection.auto_filter,
+        use_pivot_tables: protection.pivot_tables,
+        edit_scenarios: protection.edit_scenarios,
+        edit_objects: protection.edit_objects,
+        ..ProtectionOptions::new()
+    }
+}
+
+/// 印刷設定を反映する。rust_xlsxwriter の印刷範囲は 1 範囲のみなので、複数範囲は最初の範囲だけを使う
+fn apply_print_setup(worksheet: &mut Worksheet, setup: &PrintSetup) -> Result<(), XlsxError> {
+    if let Some((first, last)) = setup.print_area.as_deref().and_then(first_area) {
+        worksheet.set_print_area(first.0, first.1 as u16, last.0, last.1 as u16)?;
+    }
+    if let Some((first, last)) = setup.repeat_rows.as_deref().and_then(|r| r.split_once(':'))
+        && let (Ok(first), Ok(last)) = (first.parse::<u32>(), last.parse::<u32>())
+    {
+        worksheet.set_repeat_rows(first.saturating_sub(1), last.saturating_sub(1))?;
+    }
+    let col = |letters: &str| parse_address(&format!("{}1", letters)).map(|(_, c)| c as u16);
+    if let Some((first, last)) = setup.repeat_cols.as_deref().and_then(|r| r.split_once(':'))
+        && let (Some(first), Some(last)) = (col(first), col(last))
+    {
+        worksheet.set_repeat_columns(first, last)?;
+    }
+
+    if setup.orientation.as_deref() == Some("landscape") {
+        worksheet.set_landscape();
+    }
+    if let Some(size) = setup.paper_size.and_then(|s| u8::try_from(s).ok()) {
+        worksheet.set_paper_size(size);
+    }
+    if let Some(scale) = setup.scale.and_then(|s| u16::try_from(s).ok()) {
+        worksheet.set_print_scale(scale);
+    }
+    if setup.fit_to_width.is_some() || setup.fit_to_height.is_some() {
+        let pages = |n: Option<u32>| n.map_or(1, |n| n.min(u32::from(u16::MAX)) as u16);
+        worksheet.set_print_fit_to_pages(pages(setup.fit_to_width), pages(setup.fit_to_height));
+    }
+    if let Some(m) = &setup.margins {
+        worksheet.set_margins(m.left, m.right, m.top, m.bottom, m.header, m.footer);
+    }
+    if let Some(header) = &setup.header {
+        worksheet.set_header(header);
+    }
+    if let Some(footer) = &setup.footer {
+        worksheet.set_footer(footer);
+    }
+    worksheet.set_page_breaks(&setup.row_breaks)?;
+    worksheet.set_vertical_page_breaks(&setup.col_breaks)?;
+    Ok(())
+}
+
+fn add_table(worksheet: &mut Worksheet, table: &Table) -> Result<(), XlsxError> {
+    let mut corners = table.range.split(':').map(parse_address);
+    let (Some(Some(first)), Some(Some(last))) = (corners.next(), corners.next()) else {
+        return Err(XlsxError::ParameterError(format!(
+            "Invalid range '{}' in table '{}'",
+            table.range, table.name
+        )));
+    };
+    let columns: Vec<TableColumn> = table
+        .columns
+        .iter()
+        .map(|c| TableColumn::new().set_header(c))
+        .collect();
+    let xlsx_table = XlsxTable::new()
+        .set_name(&table.name)
+        .set_header_row(table.header_row)
+        .set_total_row(table.totals_row)
+        .set_columns(&columns)
+        .set_style(table_style(table.style.as_deref()));
+    worksheet.add_table(first.0, first.1 as u16, last.0, last.1 as u16, &xlsx_table)?;
+    Ok(())
+}
+
+/// 入力規則を追加する。複数範囲の規則は最初の範囲を基準に、残りを `set_multi_range` で指定する。
+/// rust_xlsxwriter が扱えない種類の規則は復元しない
+fn add_data_validation(
+    worksheet: &mut Worksheet,
+    validation: &DataValidation,
+) -> Result<(), XlsxError> {
+    let Some((first, last)) = first_area(&validation.range) else {
+        return Err(XlsxError::ParameterError(format!(
+            "Invalid range '{}' in data validation",
+            validation.range
+        )));
+    };
+
+    let formula1 = Formula::new(validation.formula1.as_deref().unwrap_or_default());
+    let dv = XlsxDataValidation::new();
+    let mut dv = match validation.validation_type.as_str() {
+        "none" => dv.allow_any_value(),
+        "list" => dv.allow_list_formula(formula1),
+        "custom" => dv.allow_custom(formula1),
+        kind => {
+            let formula2 = Formula::new(validation.formula2.as_deref().unwrap_or_default());
+            let Some(rule) = validation_rule(validation.operator.as_deref(), formula1, formula2)
+            else {
+                return Ok(());
+            };
+            match kind {
+                "whole" => dv.allow_whole_number_formula(rule),
+                "decimal" => dv.allow_decimal_number_formula(rule),
+                "date" => dv.allow_date_formula(rule),
+                "time" => dv.allow_time_formula(rule),
+                "textLength" => dv.allow_text_length_formula(rule),
+                _ => return Ok(()),
+            }
+        }
+    };
+
+    dv = dv
+        .ignore_blank(validation.allow_blank)
+        .show_dropdown(validation.show_dropdown)
+        .show_input_message(validation.show_input_message)
+        .show_error_message(validation.show_error_message)
+        .set_error_style(match validation.error_style.as_deref() {
+            Some("warning") => DataValidationErrorStyle::Warning,
+            Some("information") => DataValidationErrorStyle::Information,
+            _ => DataValidationErrorStyle::Stop,
+        });
+    if let Some(title) = &validation.prompt_title {
+        dv = dv.set_input_title(title)?;
+    }
+    if let Some(message) = &validation.prompt_message {
+        dv = dv.set_input_message(message)?;
+    }
+    if let Some(title) = &validation.error_title {
+        dv = dv.set_error_title(title)?;
+    }
+    if let Some(message) = &validation.error_message {
+        dv = dv.set_error_message(message)?;
+    }
+    if validation.range.split_whitespace().nth(1).is_some() {
+        dv = dv.set_multi_range(&validation.range);
+    }
+
+    worksheet.add_data_validation(first.0, first.1 as u16, last.0, last.1 as u16, &dv)?;
+    Ok(())
+}
+
+/// 空白区切りの複数範囲のうち、最初の範囲の左上・右下（単一セルなら同じ位置）
+fn first_area(range: &str) -> Option<((u32, u32), (u32, u32))> {
+    let mut corners = range.split_whitespace().next()?.split(':');
+    let first = parse_address(corners.next()?)?;
+    let last = match corners.next() {
+        Some(corner) => parse_address(corner)?,
+        None => first,
+    };
+    Some((first, last))
+}
+
+/// 条件付き書式を追加する。優先順位は rust_xlsxwriter が範囲ごとの追加順に振り直す。
+/// rust_xlsxwriter が扱えない種類のルールは復元しない
+fn add_conditional_format(
+    worksheet: &mut Worksheet,
+    rule: &ConditionalFormat,
+    styles: &[CellStyle],
+) -> Result<(), XlsxError> {
+    let Some((first, last)) = first_area(&rule.range) else {
+        return Err(XlsxError::ParameterError(format!(
+            "Invalid range '{}' in conditional format",
+            rule.range
+        )));
+    };
+    let multi_range = rule.range.split_whitespace().nth(1).is_some();
+    let format = rule
+        .style_id
+        .and_then(|id| styles.iter().find(|s| s.id == id))
+        .map(|style| apply_style(Format::new(), style));
+    let formula = |idx: usize| Formula::new(rule.formulas.get(idx).map_or("", String::as_str));
+
+    // 範囲・優先停止・書式の設定は種類ごとの型に個別に定義されているため、マクロで共通化する
+    macro_rules! add {
+        ($cf:expr) => {{
+            let mut cf = $cf.set_stop_if_true(rule.stop_if_true);
+            if multi_range {
+                cf = cf.set_multi_range(&rule.range);
+            }
+            worksheet.add_conditional_format(
+                first.0,
+                first.1 as u16,
+                last.0,
+                last.1 as u16,
+                &cf,
+            )?;
+        }};
+        (styled $cf:expr) => {{
+            let mut cf = $cf;
+            if let Some(format) = &format {
+                cf = cf.set_format(format);
+            }
+            add!(cf)
+        }};
+    }
+
+    match rule.rule_type.as_str() {
+        "cellIs" => {
+            let Some(cell_rule) = cell_rule(rule.operator.as_deref(), formula(0), formula(1))
+            else {
+                return Ok(());
+            };
+            add!(styled ConditionalFormatCell::new().set_rule(cell_rule))
+        }
+        "expression" => add!(styled ConditionalFormatFormula::new().set_rule(formula(0))),
+        "top10" => {
+            let rank = rule.rank.unwrap_or(10) as u16;
+            let top_rule = match (rule.bottom, rule.percent) {
+                (false, false) => ConditionalFormatTopRule::Top(rank),
+                (false, true) => ConditionalFormatTopRule::TopPercent(rank),
+                (true, false) => ConditionalFormatTopRule::Bottom(rank),
+                (true, true) => ConditionalFormatTopRule::BottomPercent(rank),
+            };
+            add!(styled ConditionalFormatTop::new().set_rule(top_rule))
+        }
+        "aboveAverage" => {
+            use ConditionalFormatAverageRule::*;
+            let average_rule = match (rule.std_dev, rule.below_average) {
+                (Some(1), false) => OneStandardDeviationAbove,
+                (Some(1), true) => OneStandardDeviationBelow,
+                (Some(2), false) => TwoStandardDeviationsAbove,
+                (Some(2), true) => TwoStandardDeviationsBelow,
+                (Some(_), false) => ThreeStandardDeviationsAbove,
+                (Some(_), true) => ThreeStandardDeviationsBelow,
+                (None, false) if rule.equal_average => EqualOrAboveAverage,
+                (None, true) if rule.equal_average => EqualOrBelowAverage,
+                (None, false) => AboveAverage,
+                (None, true) => BelowAverage,
+            };
+            add!(styled ConditionalFormatAverage::new().set_rule(average_rule))
+        }
+        kind @ ("containsText" | "notContainsText" | "beginsWith" | "endsWith") => {
+            let text = rule.text.clone().unwrap_or_default();
+            let text_rule = match kind {
+                "containsText" => ConditionalFormatTextRule::Contains(text),
+                "notContainsText" => ConditionalFormatTextRule::DoesNotContain(text),
+                "beginsWith" => ConditionalFormatTextRule::BeginsWith(text),
+                _ => ConditionalFormatTextRule::EndsWith(text),
+            };
+            add!(styled ConditionalFormatText::new().set_rule(text_rule))
+        }
+        "timePeriod" => {
+            let Some(date_rule) = rule.time_period.as_deref().and_then(date_rule) else {
+                return Ok(());
+            };
+            add!(styled ConditionalFormatDate::new().set_rule(date_rule))
+        }
+        "containsBlanks" => add!(styled ConditionalFormatBlank::new()),
+        "notContainsBlanks" => add!(styled ConditionalFormatBlank::new().invert()),
+        "containsErrors" => add!(styled ConditionalFormatError::new()),
+        "notContainsErrors" => add!(styled ConditionalFormatError::new().invert()),
+        "duplicateValues" => add!(styled ConditionalFormatDuplicate::new()),
+        "uniqueValues" => add!(styled ConditionalFormatDuplicate::new().invert()),
+        "colorScale" => {
+            let color = |idx: usize| rule.colors.get(idx).and_then(|c| color(c));
+            match rule.thresholds.as_slice() {
+                [min, max] => {
+                    let mut cf = ConditionalFormat2ColorScale::new();
+                    if let Some((kind, value)) = threshold(min) {
+                        cf = cf.set_minimum(kind, value);
+                    }
+                    if let Some((kind, value)) = threshold(max) {
+                        cf = cf.set_maximum(kind, value);
+                    }
+                    if let Some(c) = color(0) {
+                        cf = cf.set_minimum_color(c);
+                    }
+                    if let Some(c) = color(1) {
+                        cf = cf.set_maximum_color(c);
+                    }
+                    add!(cf)
+                }
+                [min, mid, max] => {
+                    let mut cf = ConditionalFormat3ColorScale::new();
+                    if let Some((kind, value)) = threshold(min) {
+                        cf = cf.set_minimum(kind, value);
+                    }
+                    if let Some((kind, value)) = threshold(mid) {
+                        cf = cf.set_midpoint(kind, value);
+                    }
+                    if let Some((kind, value)) = threshold(max) {
+                        cf = cf.set_maximum(kind, value);
+                    }
+                    if let Some(c) = color(0) {
+                        cf = cf.set_minimum_color(c);
+                    }
+                    if let Some(c) = color(1) {
+                        cf = cf.set_midpoint_color(c);
+                    }
+                    if let Some(c) = color(2) {
+                        cf = cf.set_maximum_color(c);
+                    }
+                    add!(cf)
+                }
+                _ => {}
+            }
+        }
+        "dataBar" => {
+            let mut cf = ConditionalFormatDataBar::new().set_bar_only(rule.hide_value);
+            if let Some((kind, value)) = rule.thresholds.first().and_then(threshold) {
+                cf = cf.set_minimum(kind, value);
+            }
+            if let Some((kind, value)) = rule.thresholds.get(1).and_then(threshold) {
+                cf = cf.set_maximum(kind, value);
+            }
+            if let Some(c) = rule.colors.first().and_then(|c| color(c)) {
+                cf = cf.set_fill_color(c);
+            }
+            add!(cf)
+        }
+        "iconSet" => {
+            let name = rule.icon_set.as_deref().unwrap_or("3TrafficLights1");
+            let Some(icon_type) = ICON_TYPES.iter().find(|t| t.to_string() == name) else {
+                return Ok(());
+            };
+            let icons: Vec<ConditionalFormatCustomIcon> = rule
+                .thresholds
+                .iter()
+                .map(|t| {
+                    let icon = ConditionalFormatCustomIcon::new().set_greater_than(t.greater_than);
+                    match threshold(t) {
+                        Some((kind, value)) => icon.set_rule(kind, value),
+                        None => icon,
+                    }
+                })
+                .collect();
+            let mut cf = ConditionalFormatIconSet::new()
+                .set_icon_type(*icon_type)
+                .reverse_icons(rule.reverse)
+                .show_icons_only(rule.hide_value);
+            if !icons.is_empty() {
+                cf = cf.set_icons(&icons);
+            }
+            add!(cf)
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// 閾値の種類と値。最小・最大・自動は rust_xlsxwriter の既定に任せる
+fn threshold(threshold: &ConditionalThreshold) -> Option<(ConditionalFormatType, Formula)> {
+    let kind = match threshold.value_type.as_str() {
+        "num" => ConditionalFormatType::Number,
+        "percent" => ConditionalFormatType::Percent,
+        "percentile" => ConditionalFormatType::Percentile,
+        "formula" => ConditionalFormatType::Formula,
+        _ => return None,
+    };
+    Some((kind, Formula::new(threshold.value.as_deref()?)))
+}
+
+fn cell_rule(
+    operator: Option<&str>,
+    formula1: Formula,
+    formula2: Formula,
+) -> Option<ConditionalFormatCellRule<Formula>> {
+    Some(match operator? {
+        "between" => ConditionalFormatCellRule::Between(formula1, formula2),
+        "notBetween" => ConditionalFormatCellRule::NotBetween(formula1, formula2),
+        "equal" => ConditionalFormatCellRule::EqualTo(formula1),
+        "notEqual" => ConditionalFormatCellRule::NotEqualTo(formula1),
+        "greaterThan" => ConditionalFormatCellRule::GreaterThan(formula1),
+        "greaterThanOrEqual" => ConditionalFormatCellRule::GreaterThanOrEqualTo(formula1),
+        "lessThan" => ConditionalFormatCellRule::LessThan(formula1),
+        "lessThanOrEqual" => ConditionalFormatCellRule::LessThanOrEqualTo(formula1),
+        _ => return None,
+    })
+}
+
+fn date_rule(period: &str) -> Option<ConditionalFormatDateRule> {
+    Some(match period {
+        "yesterday" => ConditionalFormatDateRule::Yesterday,
+        "today" => ConditionalFormatDateRule::Today,
+        "tomorrow" => ConditionalFormatDateRule::Tomorrow,
+        "last7Days" => ConditionalFormatDateRule::Last7Days,
+        "lastWeek" => ConditionalFormatDateRule::LastWeek,
+        "thisWeek" => ConditionalFormatDateRule::ThisWeek,
+        "nextWeek" => ConditionalFormatDateRule::NextWeek,
+        "lastMonth" => ConditionalFormatDateRule::LastMonth,
+        "thisMonth" => ConditionalFormatDateRule::ThisMonth,
+        "nextMonth" => ConditionalFormatDateRule::NextMonth,
+        _ => return None,
+    })
+}
+
+/// OOXML のアイコンセット名（`3TrafficLights1` など）は列挙値の表示名と一致する
+const ICON_TYPES: [ConditionalFormatIconType; 20] = [
+    ConditionalFormatIconType::ThreeArrows,
+    ConditionalFormatIconType::ThreeArrowsGray,
+    ConditionalFormatIconType::ThreeFlags,
+    ConditionalFormatIconType::ThreeTrafficLights,
+    ConditionalFormatIconType::ThreeTrafficLightsWithRim,
+    ConditionalFormatIconType::ThreeSigns,
+    ConditionalFormatIconType::ThreeSymbolsCircled,
+    ConditionalFormatIconType::ThreeSymbols,
+    ConditionalFormatIconType::ThreeStars,
+    ConditionalFormatIconType::ThreeTriangles,
+    ConditionalFormatIconType::FourArrows,
+    ConditionalFormatIconType::FourArrowsGray,
+    ConditionalFormatIconType::FourRedToBlack,
+    ConditionalFormatIconType::FourHistograms,
+    ConditionalFormatIconType::FourTrafficLights,
+    ConditionalFormatIconType::FiveArrows,
+    ConditionalFormatIconType::FiveArrowsGray,
+    ConditionalFormatIconType::FiveHistograms,
+    ConditionalFormatIconType::FiveQuadrants,
+    ConditionalFormatIconType::FiveBoxes,
+];
+
+/// OOXML の演算子名（省略時は `between`）を rust_xlsxwriter の規則にする
+fn validation_rule(
+    operator: Option<&str>,
+    formula1: Formula,
+    formula2: Formula,
+) -> Option<DataValidationRule<Formula>> {
+    Some(match operator.unwrap_or("between") {
+        "between" => DataValidationRule::Between(formula1, formula2),
+        "notBetween" => DataValidationRule::NotBetween(formula1, formula2),
+        "equal" => DataValidationRule::EqualTo(formula1),
+        "notEqual" => DataValidationRule::NotEqualTo(formula1),
+        "greaterThan" => DataValidationRule::GreaterThan(formula1),
+        "greaterThanOrEqual" => DataValidationRule::GreaterThanOrEqualTo(formula1),
+        "lessThan" => DataValidationRule::LessThan(formula1),
+        "lessThanOrEqual" => DataValidationRule::LessThanOrEqualTo(formula1),
+        _ => return None,
+    })
+}
+
+/// "TableStyleMedium2" のような名前を rust_xlsxwriter の列挙値にする（不明ならスタイルなし）
+fn table_style(name: Option<&str>) -> TableStyle {
+    const LIGHT: [TableStyle; 21] = [
+        TableStyle::Light1,
+        TableStyle::Light2,
+        TableStyle::Light3,
+        TableStyle::Light4,
+        TableStyle::Light5,
+        TableStyle::Light6,
+        TableStyle::Light7,
+        TableStyle::Light8,
+        TableStyle::Light9,
+        TableStyle::Light10,
+        TableStyle::Light11,
+        TableStyle::Light12,
+        TableStyle::Light13,
+        TableStyle::Light14,
+        TableStyle::Light15,
+        TableStyle::Light16,
+        TableStyle::Light17,
+        TableStyle::Light18,
+        TableStyle::Light19,
+        TableStyle::Light20,
+        TableStyle::Light21,
+    ];
+    const MEDIUM: [TableStyle; 28] = [
+        TableStyle::Medium1,
+        TableStyle::Medium2,
+        TableStyle::Medium3,
+        TableStyle::Medium4,
+        TableStyle::Medium5,
+        TableStyle::Medium6,
+        TableStyle::Medium7,
+        TableStyle::Medium8,
+        TableStyle::Medium9,
+        TableStyle::Medium10,
+        TableStyle::Medium11,
+        TableStyle::Medium12,
+        TableStyle::Medium13,
+        TableStyle::Medium14,
+        TableStyle::Medium15,
+        TableStyle::Medium16,
+        TableStyle::Medium17,
+        TableStyle::Medium18,
+        TableStyle::Medium19,
+        TableStyle::Medium20,
+        TableStyle::Medium21,
+        TableStyle::Medium22,
+        TableStyle::Medium23,
+        TableStyle::Medium24,
+        TableStyle::Medium25,
+        TableStyle::Medium26,
+        TableStyle::Medium27,
+        TableStyle::Medium28,
+    ];
+    const DARK: [TableStyle; 11] = [
+        TableStyle::Dark1,
+        TableStyle::Dark2,
+        TableStyle::Dark3,
+        TableStyle::Dark4,
+        TableStyle::Dark5,
+        TableStyle::Dark6,
+        TableStyle::Dark7,
+        TableStyle::Dark8,
+        TableStyle::Dark9,
+        TableStyle::Dark10,
+        TableStyle::Dark11,
+    ];
+
+    let Some(name) = name.and_then(|n| n.strip_prefix("TableStyle")) else {
+        return TableStyle::None;
+    };
+    let split = name
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(name.len());
+    let (family, number) = name.split_at(split);
+    let styles: &[TableStyle] = match family {
+        "Light" => &LIGHT,
+        "Medium" => &MEDIUM,
+        "Dark" => &DARK,
+        _ => return TableStyle::None,
+    };
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| styles.get(i))
+        .copied()
+        .unwrap_or(TableStyle::None)
+}
+
+/// `visibility` を持たない旧形式の入力では `hidden` フラグを採用する
+fn visibility(sheet: &SheetMetadata) -> SheetVisibility {
+    match sheet.visibility {
+        SheetVisibility::Visible if sheet.hidden => SheetVisibility::Hidden,
+        v => v,
+    }
+}
+
+/// 日付・期間セルのシリアル値を 1900 年基準で求める（復元先の .xlsx は常に 1900 年基準）。
+/// `serial` を持たない旧形式の入力では `value` 自体をシリアル値とみなす
+fn date_serial(cell: &CellData, date1904: bool) -> Option<f64> {
+    let serial = cell.serial.or_else(|| cell.value.parse().ok())?;
+    // 1904 年基準の日付は 1462 日ずらす（時刻のみ・期間はそのまま）
+    if date1904 && cell.data_type == CellType::DateTime && serial >= 1.0 {
+        Some(serial + 1462.0)
+    } else {
+        Some(serial)
+    }
+}
+
+/// 元の表示形式を優先し、無ければ日付・期間セルにのみ既定の書式を付ける。
+/// `style_id` があればスタイル表のフォント・塗りつぶしなども重ねる
+fn cell_format(cell: &CellData, styles: &[CellStyle]) -> Option<Format> {
+    let code = match (&cell.number_format, cell.data_type) {
+        (Some(code), _) => Some(code.as_str()),
+        (None, CellType::DateTime | CellType::Duration) => Some(date_num_format(cell)),
+        (None, _) => None,
+    };
+    let style = cell
+        .style_id
+        .and_then(|id| styles.iter().find(|s| s.id == id));
+    if code.is_none() && style.is_none() && cell.locked {
+        return None;
+    }
+
+    let mut format = Format::new();
+    if let Some(code) = code {
+        format = format.set_num_format(code);
+    }
+    if let Some(style) = style {
+        format = apply_style(format, style);
+    }
+    if !cell.locked {
+        format = format.set_unlocked();
+    }
+    Some(format)
+}
+
+/// OOXML の名前で保持した書式を rust_xlsxwriter の Format に写す（未知の名前は無視する）
+fn apply_style(mut format: Format, style: &CellStyle) -> Format {
+    if let Some(code) = &style.number_format {
+        format = format.set_num_format(code);
+    }
+    if let Some(font) = &style.font {
+        if let Some(name) = &font.name {
+            format = format.set_font_name(name);
+        }
+        if let Some(size) = font.size {
+            format = format.set_font_size(size);
+        }
+        if font.bold {
+            format = format.set_bold();
+        }
+        if font.italic {
+            format = format.set_italic();
+        }
+        if font.strike {
+            format = format.set_font_strikethrough();
+        }
+        if let Some(underline) = font.underline.as_deref().and_then(underline) {
+            format = format.set_underline(underline);
+        }
+        match font.script.as_deref() {
+            Some("superscript") => format = format.set_font_script(FormatScript::Superscript),
+            Some("subscript") => format = format.set_font_script(FormatScript::Subscript),
+            _ => {}
+        }
+        if let Some(color) = font.color.as_deref().and_then(color) {
+            format = format.set_font_color(color);
+        }
+    }
+
+    if let Some(fill) = &style.fill {
+        let pattern = pattern(&fill.pattern);
+        let fg = fill.fg_color.as_deref().and_then(color);
+        let bg = fill.bg_color.as_deref().and_then(color);
+        // rust_xlsxwriter は単色塗りで前景・背景の両方があると役割を入れ替えて書き出す
+        let (fg, bg) = match (pattern, fg, bg) {
+            (Some(FormatPattern::Solid), Some(fg), Some(bg)) => (Some(bg), Some(fg)),
+            _ => (fg, bg),
+        };
+        if let Some(pattern) = pattern {
+            format = format.set_pattern(pattern);
+        }
+        if let Some(fg) = fg {
+            format = format.set_foreground_color(fg);
+        }
+        if let Some(bg) = bg {
+            format = format.set_background_color(bg);
+        }
+    }
+
+    if let Some(border) = &style.border {
+        let edge = |edge: &Option<BorderEdge>| {
+            edge.as_ref()
+                .and_then(|e| Some((border_style(&e.style)?, e.color.as_deref().and_then(color))))
+        };
+        if let Some((style, color)) = edge(&border.left) {
+            format = format.set_border_left(style);
+            if let Some(color) = color {
+                format = format.set_border_left_color(color);
+            }
+        }
+        if let Some((style, color)) = edge(&border.right) {
+            format = format.set_border_right(style);
+            if let Some(color) = color {
+                format = format.set_border_right_color(color);
+            }
+        }
+        if let Some((style, color)) = edge(&border.top) {
+            format = format.set_border_top(style);
+            if let Some(color) = color {
+                format = format.set_border_top_color(color);
+            }
+        }
+        if let Some((style, color)) = edge(&border.bottom) {
+            format = format.set_border_bottom(style);
+            if let Some(color) = color {
+                format = format.set_border_bottom_color(color);
+            }
+        }
+        if let Some((style, color)) = edge(&border.diagonal) {
+            let direction = match (border.diagonal_up, border.diagonal_down) {
+                (true, true) => FormatDiagonalBorder::BorderUpDown,
+                (true, false) => FormatDiagonalBorder::BorderUp,
+                (false, true) => FormatDiagonalBorder::BorderDown,
+                (false, false) => FormatDiagonalBorder::None,
+            };
+            format = format
+                .set_border_diagonal(style)
+                .set_border_diagonal_type(direction);
+            if let Some(color) = color {
+                format = format.set_border_diagonal_color(color);
+            }
+        }
+    }
+
+    if let Some(alignment) = &style.alignment {
+        if let Some(align) = alignment.horizontal.as_deref().and_then(horizontal) {
+            format = format.set_align(align);
+        }
+        if let Some(align) = alignment.vertical.as_deref().and_then(vertical) {
+            format = format.set_align(align);
+        }
+        if alignment.wrap_text {
+            format = format.set_text_wrap();
+        }
+        if alignment.shrink_to_fit {
+            format = format.set_shrink();
+        }
+        if let Some(indent) = alignment.indent {
+            format = format.set_indent(indent.min(u8::MAX as u32) as u8);
+        }
+        // OOXML は 91〜180 で下向きの角度、255 で縦書きを表す
+        match alignment.text_rotation {
+            Some(r @ 1..=90) => format = format.set_rotation(r as i16),
+            Some(r @ 91..=180) => format = format.set_rotation(90 - r as i16),
+            Some(255) => format = format.set_rotation(270),
+            _ => {}
+        }
+    }
+
+    if let Some(protection) = &style.protection {
+        if !protection.locked {
+            format = format.set_unlocked();
+        }
+        if protection.hidden {
+            format = format.set_hidden();
+        }
+    }
+
+    format
+}
+
+/// `#RRGGBB` 形式の色
+fn color(s: &str) -> Option<Color> {
+    let hex = s.strip_prefix('#')?;
+    (hex.len() == 6)
+        .then(|| u32::from_str_radix(hex, 16).ok())
+        .flatten()
+        .map(Color::RGB)
+}
+
+fn underline(name: &str) -> Option<FormatUnderline> {
+    Some(match name {
+        "single" => FormatUnderline::Single,
+        "double" => FormatUnderline::Double,
+        "singleAccounting" => FormatUnderline::SingleAccounting,
+        "doubleAccounting" => FormatUnderline::DoubleAccounting,
+        _ => return None,
+    })
+}
+
+fn pattern(name: &str) -> Option<FormatPattern> {
+    Some(match name {
+        "solid" => FormatPattern::Solid,
+        "mediumGray" => FormatPattern::MediumGray,
+        "darkGray" => FormatPattern::DarkGray,
+        "lightGray" => FormatPattern::LightGray,
+        "darkHorizontal" => FormatPattern::DarkHorizontal,
+        "darkVertical" => FormatPattern::DarkVertical,
+        "darkDown" => FormatPattern::DarkDown,
+        "darkUp" => FormatPattern::DarkUp,
+        "darkGrid" => FormatPattern::DarkGrid,
+        "darkTrellis" => FormatPattern::DarkTrellis,
+        "lightHorizontal" => FormatPattern::LightHorizontal,
+        "lightVertical" => FormatPattern::LightVertical,
+        "lightDown" => FormatPattern::LightDown,
+        "lightUp" => FormatPattern::LightUp,
+        "lightGrid" => FormatPattern::LightGrid,
+        "lightTrellis" => FormatPattern::LightTrellis,
+        "gray125" => FormatPattern::Gray125,
+        "gray0625" => FormatPattern::Gray0625,
+        _ => return None,
+    })
+}
+
+fn border_style(name: &str) -> Option<FormatBorder> {
+    Some(match name {
+        "thin" => FormatBorder::Thin,
+        "medium" => FormatBorder::Medium,
+        "dashed" => FormatBorder::Dashed,
+        "dotted" => FormatBorder::Dotted,
+        "thick" => FormatBorder::Thick,
+        "double" => FormatBorder::Double,
+        "hair" => FormatBorder::Hair,
+        "mediumDashed" => FormatBorder::MediumDashed,
+        "dashDot" => FormatBorder::DashDot,
+        "mediumDashDot" => FormatBorder::MediumDashDot,
+        "dashDotDot" => FormatBorder::DashDotDot,
+        "mediumDashDotDot" => FormatBorder::MediumDashDotDot,
+        "slantDashDot" => FormatBorder::SlantDashDot,
+        _ => return None,
+    })
+}
+
+fn horizontal(name: &str) -> Option<FormatAlign> {
+    Some(match name {
+        "left" => FormatAlign::Left,
+        "center" => FormatAlign::Center,
+        "right" => FormatAlign::Right,
+        "fill" => FormatAlign::Fill,
+        "justify" => FormatAlign::Justify,
+        "centerContinuous" => FormatAlign::CenterAcross,
+        "distributed" => FormatAlign::Distributed,
+        _ => return None,
+    })
+}
+
+fn vertical(name: &str) -> Option<FormatAlign> {
+    Some(match name {
+        "top" => FormatAlign::Top,
+        "bottom" => FormatAlign::Bottom,
+        "center" => FormatAlign::VerticalCenter,
+        "justify" => FormatAlign::VerticalJustify,
+        "distributed" => FormatAlign::VerticalDistributed,
+        _ => return None,
+    })
+}
+
+/// ISO 8601 表記の形（日付のみ・時刻のみ・日時）から表示書式を選ぶ
+fn date_num_format(cell: &CellData) -> &'static str {
+    let value = cell.value.as_str();
+    if cell.data_type == CellType::Duration {
+        "[h]:mm:ss"
+    } else if value.contains('T') || !value.contains(['-', ':']) {
+        "yyyy-mm-dd hh:mm:ss"
+    } else if value.contains(':') {
+        "hh:mm:ss"
+    } else {
+        "yyyy-mm-dd"
+    }
+}
+
+/// 数式のキャッシュ値を rust_xlsxwriter が型を判別できる表記に揃える
+fn formula_result(cell: &CellData, date1904: bool) -> String {
+    match cell.data_type {
+        CellType::Boolean if cell.value == "true" => "TRUE".into(),
+        CellType::Boolean if cell.value == "false" => "FALSE".into(),
+        CellType::Error => error_literal(&cell.value)
+            .unwrap_or(&cell.value)
+            .to_string(),
+        CellType::DateTime | CellType::Duration => date_serial(cell, date1904)
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| cell.value.clone()),
+        _ => cell.value.clone(),
+    }
+}
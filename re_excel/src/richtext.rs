@@ -0,0 +1,75 @@
+use crate::model::{PhoneticRun, TextRun, parse_address};
+use crate::ooxml::{Element, Package};
+use crate::styles::{self, Colors};
+use std::collections::HashMap;
+
+/// 書式付きの区間またはふりがなを持つ文字列
+#[derive(Clone, Default)]
+pub struct RichText {
+    pub runs: Vec<TextRun>,
+    pub phonetic: Vec<PhoneticRun>,
+}
+
+/// 共有文字列（`sharedStrings.xml`）を並び順に読む。書式もふりがなも無い文字列は `None`
+pub fn read_shared_strings(package: &mut Package, colors: &Colors) -> Vec<Option<RichText>> {
+    let path = package
+        .relationships("xl/workbook.xml")
+        .into_iter()
+        .find(|r| r.is("sharedStrings"))
+        .map_or_else(|| "xl/sharedStrings.xml".to_string(), |r| r.target);
+    let Some(sst) = package.part(&path) else {
+        return Vec::new();
+    };
+    sst.children_named("si")
+        .map(|si| read_rich_text(si, colors))
+        .collect()
+}
+
+/// ワークシート内のセル（0-based の行・列）と書式付き文字列の対応。
+/// 共有文字列（`t="s"`）とインライン文字列（`t="inlineStr"`）が対象
+pub fn cell_rich_text(
+    sheet: &Element,
+    shared: &[Option<RichText>],
+    colors: &Colors,
+) -> HashMap<(u32, u32), RichText> {
+    sheet
+        .child("sheetData")
+        .into_iter()
+        .flat_map(|d| d.children_named("row"))
+        .flat_map(|r| r.children_named("c"))
+        .filter_map(|c| {
+            let position = parse_address(c.attr("r")?)?;
+            let text = match c.attr("t")? {
+                "s" => {
+                    let idx: usize = c.child("v")?.text.trim().parse().ok()?;
+                    shared.get(idx)?.clone()?
+                }
+                "inlineStr" => read_rich_text(c.child("is")?, colors)?,
+                _ => return None,
+            };
+            Some((position, text))
+        })
+        .collect()
+}
+
+/// `<si>` / `<is>` を読む。区間（`<r>`）が無く、ふりがなも無ければ `None`
+fn read_rich_text(item: &Element, colors: &Colors) -> Option<RichText> {
+    let runs: Vec<TextRun> = item
+        .children_named("r")
+        .map(|r| TextRun {
+            text: r.child("t").map(|t| t.text.clone()).unwrap_or_default(),
+            font: r.child("rPr").map(|f| styles::read_font(f, colors)),
+        })
+        .collect();
+    let phonetic: Vec<PhoneticRun> = item
+        .children_named("rPh")
+        .filter_map(|p| {
+            Some(PhoneticRun {
+                text: p.child("t")?.text.clone(),
+                start: p.attr("sb")?.parse().ok()?,
+                end: p.attr("eb")?.parse().ok()?,
+            })
+        })
+        .collect();
+    (!runs.is_empty() || !phonetic.is_empty()).then_some(RichText { runs, phonetic })
+}
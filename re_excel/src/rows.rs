@@ -0,0 +1,252 @@
+use crate::model::{CellData, CellType, Table, Workbook, col_to_letter, parse_address};
+use crate::typed::{TypedValue, ValueMode, typed_value};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+/// テーブルの出力方法。既定は従来どおり `cells` のみ
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum TableMode {
+    #[default]
+    Cells,
+    /// テーブルごとに見出しをキーにした行オブジェクトの配列（`table_rows`）を出力し、
+    /// 行オブジェクトから作り直せるセルは `cells` から除く。逆変換では `table_rows` から
+    /// セルを復元する。キーが任意の見出しになるため、JSON / YAML 出力でのみ使える
+    Rows,
+}
+
+impl TableMode {
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "cells" => Some(TableMode::Cells),
+            "rows" => Some(TableMode::Rows),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TableRows<'a> {
+    name: &'a str,
+    sheet: &'a str,
+    rows: Vec<RowObject<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    totals: Option<RowObject<'a>>,
+}
+
+/// 列見出しをキーにした 1 行分の値（キーは列の並び順のまま出力する）
+pub struct RowObject<'a> {
+    columns: &'a [String],
+    values: Vec<RowValue<'a>>,
+}
+
+/// string モードでは文字列、typed モードでは種類付きの値。セルが無ければ null
+#[derive(Serialize)]
+#[serde(untagged)]
+enum RowValue<'a> {
+    Text(&'a str),
+    Typed(TypedValue<'a>),
+    Null,
+}
+
+impl Serialize for RowObject<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.values.len()))?;
+        for (idx, value) in self.values.iter().enumerate() {
+            map.serialize_entry(&column_key(self.columns, idx), value)?;
+        }
+        map.end()
+    }
+}
+
+/// 見出し行・集計行を除いたデータ行を行オブジェクトにする。セルが無い位置は null
+pub fn table_rows<'a>(
+    tables: &'a [Table],
+    cells: &'a [CellData],
+    mode: ValueMode,
+) -> Vec<TableRows<'a>> {
+    let by_position: HashMap<_, _> = cells
+        .iter()
+        .map(|c| ((c.sheet.as_str(), c.row - 1, c.col - 1), c))
+        .collect();
+
+    tables
+        .iter()
+        .filter_map(|table| {
+            let (first, last) = table_bounds(table)?;
+            let row_object = |row: u32| RowObject {
+                columns: &table.columns,
+                values: (first.1..=last.1)
+                    .map(
+                        |col| match by_position.get(&(table.sheet.as_str(), row, col)) {
+                            None => RowValue::Null,
+                            Some(cell) => row_value(cell, mode),
+                        },
+                    )
+                    .collect(),
+            };
+
+            let data_first = first.0 + table.header_row as u32;
+            let data_last = last.0.saturating_sub(table.totals_row as u32);
+            Some(TableRows {
+                name: &table.name,
+                sheet: &table.sheet,
+                rows: (data_first..=data_last).map(row_object).collect(),
+                totals: table.totals_row.then(|| row_object(last.0)),
+            })
+        })
+        .collect()
+}
+
+fn row_value(cell: &CellData, mode: ValueMode) -> RowValue<'_> {
+    match mode {
+        ValueMode::String => RowValue::Text(&cell.value),
+        ValueMode::Typed => RowValue::Typed(typed_value(cell)),
+    }
+}
+
+/// 見出しが足りない列は `Column1` のように位置で呼ぶ
+fn column_key(columns: &[String], idx: usize) -> Cow<'_, str> {
+    match columns.get(idx) {
+        Some(column) => Cow::Borrowed(column),
+        None => Cow::Owned(format!("Column{}", idx + 1)),
+    }
+}
+
+/// rows モードで `cells` から除けるセルか。見出し行は `Table::columns` から、
+/// データ行・集計行は `table_rows` の値から逆変換で元どおりに作り直せるものに限る
+/// （数式・書式・日付などを持つセルは `cells` に残す）
+pub fn rebuilt_from_rows(tables: &[Table], cell: &CellData, mode: ValueMode) -> bool {
+    let plain = cell.formula.is_none()
+        && cell.number_format.is_none()
+        && cell.style_id.is_none()
+        && cell.runs.is_empty()
+        && cell.phonetic.is_empty()
+        && cell.pivot_table.is_none()
+        && cell.locked;
+    let position = (cell.row - 1, cell.col - 1);
+    let Some((table, first, _)) = tables
+        .iter()
+        .filter(|t| t.sheet == cell.sheet)
+        .filter_map(|t| Some((t, table_bounds(t)?)))
+        .map(|(t, (first, last))| (t, first, last))
+        .find(|(_, first, last)| {
+            (first.0..=last.0).contains(&position.0) && (first.1..=last.1).contains(&position.1)
+        })
+    else {
+        return false;
+    };
+
+    if !plain {
+        false
+    } else if table.header_row && position.0 == first.0 {
+        cell.data_type == CellType::String
+            && table.columns.get((position.1 - first.1) as usize) == Some(&cell.value)
+    } else {
+        serde_json::to_value(row_value(cell, mode))
+            .ok()
+            .and_then(|value| row_cell(&value))
+            .is_some_and(|(data_type, value)| data_type == cell.data_type && value == cell.value)
+    }
+}
+
+/// 逆変換の入力の `table_rows`
+#[derive(Deserialize)]
+pub struct TableRowsInput {
+    name: String,
+    #[serde(default)]
+    rows: Vec<Map<String, Value>>,
+    #[serde(default)]
+    totals: Option<Map<String, Value>>,
+}
+
+/// `table_rows` からセルを作り直す。`cells` にあるセルはそちらを優先する
+pub fn restore_table_cells(wb: &mut Workbook, table_rows: Vec<TableRowsInput>) {
+    let mut occupied: HashSet<_> = wb
+        .cells
+        .iter()
+        .map(|c| (c.sheet.clone(), c.row, c.col))
+        .collect();
+    let mut rebuilt = Vec::new();
+
+    for input in table_rows {
+        let Some(table) = wb.tables.iter().find(|t| t.name == input.name) else {
+            continue;
+        };
+        let Some((first, last)) = table_bounds(table) else {
+            continue;
+        };
+        let mut put = |row: u32, col: u32, (data_type, value): (CellType, String)| {
+            if occupied.insert((table.sheet.clone(), row + 1, col + 1)) {
+                rebuilt.push(CellData {
+                    sheet: table.sheet.clone(),
+                    address: format!("{}{}", col_to_letter(col + 1), row + 1),
+                    row: row + 1,
+                    col: col + 1,
+                    data_type,
+                    value,
+                    formula: None,
+                    serial: None,
+                    number_format: None,
+                    display_text: None,
+                    style_id: None,
+                    runs: Vec::new(),
+                    phonetic: Vec::new(),
+                    pivot_table: None,
+                    locked: true,
+                });
+            }
+        };
+
+        if table.header_row {
+            for (col, column) in (first.1..=last.1).zip(&table.columns) {
+                put(first.0, col, (CellType::String, column.clone()));
+            }
+        }
+        let data_first = first.0 + table.header_row as u32;
+        let data_last = last.0.saturating_sub(table.totals_row as u32);
+        let totals = input.totals.as_ref().filter(|_| table.totals_row);
+        let rows = input
+            .rows
+            .iter()
+            .zip(data_first..=data_last)
+            .chain(totals.map(|object| (object, last.0)));
+        for (object, row) in rows {
+            for (idx, col) in (first.1..=last.1).enumerate() {
+                let key = column_key(&table.columns, idx);
+                if let Some(cell) = object.get(key.as_ref()).and_then(row_cell) {
+                    put(row, col, cell);
+                }
+            }
+        }
+    }
+    wb.cells.extend(rebuilt);
+}
+
+/// 行オブジェクトの値をセルの種類と文字列に戻す。string モードの文字列は数値として
+/// 読めれば数値とみなす。作り直せない値（null・日付・エラーなど）は None
+fn row_cell(value: &Value) -> Option<(CellType, String)> {
+    match value {
+        Value::Bool(b) => Some((CellType::Boolean, b.to_string())),
+        Value::Number(n) => Some((CellType::Number, n.to_string())),
+        Value::String(s) if s.parse::<f64>().is_ok_and(f64::is_finite) => {
+            Some((CellType::Number, s.clone()))
+        }
+        Value::String(s) => Some((CellType::String, s.clone())),
+        Value::Object(typed) => match (typed.get("type")?.as_str()?, typed.get("value")?) {
+            ("number", Value::Number(n)) => Some((CellType::Number, n.to_string())),
+            ("bool", Value::Bool(b)) => Some((CellType::Boolean, b.to_string())),
+            ("string", Value::String(s)) => Some((CellType::String, s.clone())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// テーブル範囲の左上・右下（0-based）
+fn table_bounds(table: &Table) -> Option<((u32, u32), (u32, u32))> {
+    let (first, last) = table.range.split_once(':')?;
+    Some((parse_address(first)?, parse_address(last)?))
+}
@@ -0,0 +1,1346 @@
+use crate::model::{
+    AlignmentStyle, BorderEdge, BorderStyle, CellData, CellStyle, CellType, Chart, ChartSeries,
+    ColumnLayout, Comment, CommentReply, ConditionalFormat, ConditionalThreshold, DataValidation,
+    DefinedName, Drawing, DrawingKind, FillStyle, FontStyle, FreezePanes, Hyperlink, MergedRange,
+    PageMargins, PasswordHash, PhoneticRun, PivotDataField, PivotTable, PrintSetup,
+    ProtectionStyle, RowLayout, SheetKind, SheetMetadata, SheetProtection, SheetVisibility,
+    SourceFormat, Table, TextRun, Workbook, WorkbookProtection,
+};
+
+pub fn to_sql(wb: &Workbook) -> String {
+    let mut sql = String::new();
+    sql.push_str("CREATE TABLE workbook (source_format TEXT, date1904 INTEGER);\n");
+    sql.push_str(&format!(
+        "INSERT INTO workbook VALUES ({},{});\n",
+        wb.source_format
+            .map(|f| quote(f.as_str()))
+            .unwrap_or_else(|| "NULL".into()),
+        flag(wb.date1904)
+    ));
+
+    sql.push_str(
+        "CREATE TABLE sheet_metadata (name TEXT, sheet_index INTEGER, hidden INTEGER, visibility TEXT, sheet_type TEXT, \
+         tab_color TEXT, zoom INTEGER, hide_gridlines INTEGER, default_row_height REAL, default_col_width REAL, \
+         freeze_rows INTEGER, freeze_cols INTEGER, freeze_top_left_cell TEXT);\n",
+    );
+    sql.push_str(
+        "CREATE TABLE column_layout (sheet TEXT, first_col INTEGER, last_col INTEGER, width REAL, hidden INTEGER, outline_level INTEGER, collapsed INTEGER);\n",
+    );
+    sql.push_str(
+        "CREATE TABLE row_layout (sheet TEXT, row INTEGER, height REAL, hidden INTEGER, outline_level INTEGER, collapsed INTEGER);\n",
+    );
+    for sheet in &wb.sheets {
+        let freeze = sheet.freeze_panes.as_ref();
+        sql.push_str(&format!(
+            "INSERT INTO sheet_metadata VALUES ({},{},{},'{}','{}',{},{},{},{},{},{},{},{});\n",
+            quote(&sheet.name),
+            sheet.index,
+            sheet.hidden as u8,
+            sheet.visibility.as_str(),
+            sheet.sheet_type.as_str(),
+            nullable(sheet.tab_color.as_deref()),
+            number(sheet.zoom),
+            sheet.hide_gridlines as u8,
+            number(sheet.default_row_height),
+            number(sheet.default_col_width),
+            number(freeze.map(|f| f.rows)),
+            number(freeze.map(|f| f.cols)),
+            nullable(freeze.and_then(|f| f.top_left_cell.as_deref()))
+        ));
+        for col in &sheet.columns {
+            sql.push_str(&format!(
+                "INSERT INTO column_layout VALUES ({},{},{},{},{},{},{});\n",
+                quote(&sheet.name),
+                col.first_col,
+                col.last_col,
+                number(col.width),
+                col.hidden as u8,
+                col.outline_level,
+                col.collapsed as u8
+            ));
+        }
+        for row in &sheet.rows {
+            sql.push_str(&format!(
+                "INSERT INTO row_layout VALUES ({},{},{},{},{},{});\n",
+                quote(&sheet.name),
+                row.row,
+                number(row.height),
+                row.hidden as u8,
+                row.outline_level,
+                row.collapsed as u8
+            ));
+        }
+    }
+
+    // 余白は既定値のときすべて NULL
+    sql.push_str(
+        "CREATE TABLE print_setup (sheet TEXT, print_area TEXT, repeat_rows TEXT, repeat_cols TEXT, orientation TEXT, paper_size INTEGER, \
+         scale INTEGER, fit_to_width INTEGER, fit_to_height INTEGER, margin_left REAL, margin_right REAL, margin_top REAL, \
+         margin_bottom REAL, margin_header REAL, margin_footer REAL, header TEXT, footer TEXT);\n",
+    );
+    sql.push_str("CREATE TABLE page_break (sheet TEXT, direction TEXT, position INTEGER);\n");
+    for sheet in &wb.sheets {
+        let Some(setup) = &sheet.print_setup else {
+            continue;
+        };
+        let margins = setup.margins.as_ref();
+        sql.push_str(&format!(
+            "INSERT INTO print_setup VALUES ({},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{});\n",
+            quote(&sheet.name),
+            nullable(setup.print_area.as_deref()),
+            nullable(setup.repeat_rows.as_deref()),
+            nullable(setup.repeat_cols.as_deref()),
+            nullable(setup.orientation.as_deref()),
+            number(setup.paper_size),
+            number(setup.scale),
+            number(setup.fit_to_width),
+            number(setup.fit_to_height),
+            number(margins.map(|m| m.left)),
+            number(margins.map(|m| m.right)),
+            number(margins.map(|m| m.top)),
+            number(margins.map(|m| m.bottom)),
+            number(margins.map(|m| m.header)),
+            number(margins.map(|m| m.footer)),
+            nullable(setup.header.as_deref()),
+            nullable(setup.footer.as_deref())
+        ));
+        let breaks = [("row", &setup.row_breaks), ("col", &setup.col_breaks)];
+        for (direction, positions) in breaks {
+            for position in positions {
+                sql.push_str(&format!(
+                    "INSERT INTO page_break VALUES ({},'{}',{});\n",
+                    quote(&sheet.name),
+                    direction,
+                    position
+                ));
+            }
+        }
+    }
+
+    sql.push_str(&format!(
+        "CREATE TABLE workbook_protection (lock_structure INTEGER, lock_windows INTEGER, {});\n",
+        PASSWORD_COLUMNS
+    ));
+    if let Some(protection) = &wb.protection {
+        sql.push_str(&format!(
+            "INSERT INTO workbook_protection VALUES ({},{},{});\n",
+            protection.structure as u8,
+            protection.windows as u8,
+            password_values(protection.has_password, protection.password.as_ref()).join(",")
+        ));
+    }
+    // 操作の列は保護中でも許可されるかどうか
+    sql.push_str(&format!(
+        "CREATE TABLE sheet_protection (sheet TEXT, {}, select_locked_cells INTEGER, select_unlocked_cells INTEGER, \
+         format_cells INTEGER, format_columns INTEGER, format_rows INTEGER, insert_columns INTEGER, insert_rows INTEGER, \
+         insert_hyperlinks INTEGER, delete_columns INTEGER, delete_rows INTEGER, sort INTEGER, auto_filter INTEGER, \
+         pivot_tables INTEGER, edit_objects INTEGER, edit_scenarios INTEGER);\n",
+        PASSWORD_COLUMNS
+    ));
+    for sheet in &wb.sheets {
+        let Some(p) = &sheet.protection else {
+            continue;
+        };
+        let allowed = [
+            p.select_locked_cells,
+            p.select_unlocked_cells,
+            p.format_cells,
+            p.format_columns,
+            p.format_rows,
+            p.insert_columns,
+            p.insert_rows,
+            p.insert_hyperlinks,
+            p.delete_columns,
+            p.delete_rows,
+            p.sort,
+            p.auto_filter,
+            p.pivot_tables,
+            p.edit_objects,
+            p.edit_scenarios,
+        ];
+        sql.push_str(&format!(
+            "INSERT INTO sheet_protection VALUES ({},{},{});\n",
+            quote(&sheet.name),
+            password_values(p.has_password, p.password.as_ref()).join(","),
+            allowed.map(|a| (a as u8).to_string()).join(",")
+        ));
+    }
+
+    sql.push_str(
+        "CREATE TABLE cell_data (sheet TEXT, address TEXT, row INTEGER, col INTEGER, data_type TEXT, value TEXT, formula TEXT, serial REAL, number_format TEXT, display_text TEXT, style_id INTEGER, pivot_table TEXT, \
+         locked INTEGER);\n",
+    );
+    for cell in &wb.cells {
+        sql.push_str(&format!(
+            "INSERT INTO cell_data VALUES ({},'{}',{},{},{},{},{},{},{},{},{},{},{});\n",
+            quote(&cell.sheet),
+            cell.address,
+            cell.row,
+            cell.col,
+            quote(cell.data_type.as_str()),
+            quote(&cell.value),
+            nullable(cell.formula.as_deref()),
+            number(cell.serial),
+            nullable(cell.number_format.as_deref()),
+            nullable(cell.display_text.as_deref()),
+            number(cell.style_id),
+            nullable(cell.pivot_table.as_deref()),
+            cell.locked as u8
+        ));
+    }
+
+    // 区間・ふりがなはシートとセル番地でセルに紐付ける
+    sql.push_str(&format!(
+        "CREATE TABLE text_run (sheet TEXT, address TEXT, run_index INTEGER, text TEXT, {});\n",
+        FONT_COLUMNS
+    ));
+    sql.push_str(
+        "CREATE TABLE phonetic_run (sheet TEXT, address TEXT, start_index INTEGER, end_index INTEGER, text TEXT);\n",
+    );
+    for cell in &wb.cells {
+        for (idx, run) in cell.runs.iter().enumerate() {
+            sql.push_str(&format!(
+                "INSERT INTO text_run VALUES ({},'{}',{},{},{});\n",
+                quote(&cell.sheet),
+                cell.address,
+                idx,
+                quote(&run.text),
+                font_values(run.font.as_ref()).join(",")
+            ));
+        }
+        for phonetic in &cell.phonetic {
+            sql.push_str(&format!(
+                "INSERT INTO phonetic_run VALUES ({},'{}',{},{},{});\n",
+                quote(&cell.sheet),
+                cell.address,
+                phonetic.start,
+                phonetic.end,
+                quote(&phonetic.text)
+            ));
+        }
+    }
+
+    sql.push_str(&format!("CREATE TABLE cell_style ({});\n", STYLE_COLUMNS));
+    for style in &wb.styles {
+        sql.push_str(&format!(
+            "INSERT INTO cell_style VALUES ({});\n",
+            style_values(style).join(",")
+        ));
+    }
+
+    sql.push_str("CREATE TABLE merged_range (sheet TEXT, start_cell TEXT, end_cell TEXT);\n");
+    for merged in &wb.merged_ranges {
+        sql.push_str(&format!(
+            "INSERT INTO merged_range VALUES ({},{},{});\n",
+            quote(&merged.sheet),
+            quote(&merged.start),
+            quote(&merged.end)
+        ));
+    }
+
+    // スレッドの先頭を reply_index 0、返信を 1 からの連番で 1 行ずつ出力する
+    sql.push_str(
+        "CREATE TABLE comment (sheet TEXT, address TEXT, reply_index INTEGER, author TEXT, text TEXT);\n",
+    );
+    for comment in &wb.comments {
+        let thread = std::iter::once((comment.author.as_deref(), &comment.text)).chain(
+            comment
+                .replies
+                .iter()
+                .map(|r| (r.author.as_deref(), &r.text)),
+        );
+        for (idx, (author, text)) in thread.enumerate() {
+            sql.push_str(&format!(
+                "INSERT INTO comment VALUES ({},{},{},{},{});\n",
+                quote(&comment.sheet),
+                quote(&comment.address),
+                idx,
+                nullable(author),
+                quote(text)
+            ));
+        }
+    }
+
+    sql.push_str(
+        "CREATE TABLE hyperlink (sheet TEXT, cell_range TEXT, target TEXT, tooltip TEXT, internal INTEGER);\n",
+    );
+    for link in &wb.hyperlinks {
+        sql.push_str(&format!(
+            "INSERT INTO hyperlink VALUES ({},{},{},{},{});\n",
+            quote(&link.sheet),
+            quote(&link.range),
+            quote(&link.target),
+            nullable(link.tooltip.as_deref()),
+            link.internal as u8
+        ));
+    }
+
+    sql.push_str(
+        "CREATE TABLE defined_name (name TEXT, scope TEXT, refers_to TEXT, hidden INTEGER);\n",
+    );
+    for name in &wb.defined_names {
+        sql.push_str(&format!(
+            "INSERT INTO defined_name VALUES ({},{},{},{});\n",
+            quote(&name.name),
+            nullable(name.scope.as_deref()),
+            quote(&name.refers_to),
+            name.hidden as u8
+        ));
+    }
+
+    sql.push_str(
+        "CREATE TABLE excel_table (name TEXT, sheet TEXT, cell_range TEXT, header_row INTEGER, totals_row INTEGER, style TEXT);\n",
+    );
+    sql.push_str("CREATE TABLE table_column (table_name TEXT, column_index INTEGER, name TEXT);\n");
+    for table in &wb.tables {
+        sql.push_str(&format!(
+            "INSERT INTO excel_table VALUES ({},{},{},{},{},{});\n",
+            quote(&table.name),
+            quote(&table.sheet),
+            quote(&table.range),
+            table.header_row as u8,
+            table.totals_row as u8,
+            nullable(table.style.as_deref())
+        ));
+        for (idx, column) in table.columns.iter().enumerate() {
+            sql.push_str(&format!(
+                "INSERT INTO table_column VALUES ({},{},{});\n",
+                quote(&table.name),
+                idx,
+                quote(column)
+            ));
+        }
+    }
+
+    sql.push_str(
+        "CREATE TABLE data_validation (sheet TEXT, cell_range TEXT, validation_type TEXT, operator TEXT, formula1 TEXT, formula2 TEXT, \
+         allow_blank INTEGER, show_dropdown INTEGER, show_input_message INTEGER, show_error_message INTEGER, \
+         error_style TEXT, error_title TEXT, error_message TEXT, prompt_title TEXT, prompt_message TEXT);\n",
+    );
+    for dv in &wb.data_validations {
+        sql.push_str(&format!(
+            "INSERT INTO data_validation VALUES ({},{},{},{},{},{},{},{},{},{},{},{},{},{},{});\n",
+            quote(&dv.sheet),
+            quote(&dv.range),
+            quote(&dv.validation_type),
+            nullable(dv.operator.as_deref()),
+            nullable(dv.formula1.as_deref()),
+            nullable(dv.formula2.as_deref()),
+            dv.allow_blank as u8,
+            dv.show_dropdown as u8,
+            dv.show_input_message as u8,
+            dv.show_error_message as u8,
+            nullable(dv.error_style.as_deref()),
+            nullable(dv.error_title.as_deref()),
+            nullable(dv.error_message.as_deref()),
+            nullable(dv.prompt_title.as_deref()),
+            nullable(dv.prompt_message.as_deref())
+        ));
+    }
+
+    // 式・閾値・色はシートと優先順位でルールに紐付ける
+    sql.push_str(
+        "CREATE TABLE conditional_format (sheet TEXT, cell_range TEXT, priority INTEGER, rule_type TEXT, stop_if_true INTEGER, style_id INTEGER, \
+         operator TEXT, text TEXT, time_period TEXT, rank INTEGER, percent INTEGER, bottom INTEGER, below_average INTEGER, equal_average INTEGER, \
+         std_dev INTEGER, icon_set TEXT, reverse INTEGER, hide_value INTEGER);\n",
+    );
+    sql.push_str(
+        "CREATE TABLE conditional_format_formula (sheet TEXT, priority INTEGER, formula_index INTEGER, formula TEXT);\n",
+    );
+    sql.push_str(
+        "CREATE TABLE conditional_format_threshold (sheet TEXT, priority INTEGER, threshold_index INTEGER, value_type TEXT, value TEXT, greater_than INTEGER);\n",
+    );
+    sql.push_str(
+        "CREATE TABLE conditional_format_color (sheet TEXT, priority INTEGER, color_index INTEGER, color TEXT);\n",
+    );
+    for cf in &wb.conditional_formats {
+        sql.push_str(&format!(
+            "INSERT INTO conditional_format VALUES ({},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{});\n",
+            quote(&cf.sheet),
+            quote(&cf.range),
+            cf.priority,
+            quote(&cf.rule_type),
+            cf.stop_if_true as u8,
+            number(cf.style_id),
+            nullable(cf.operator.as_deref()),
+            nullable(cf.text.as_deref()),
+            nullable(cf.time_period.as_deref()),
+            number(cf.rank),
+            cf.percent as u8,
+            cf.bottom as u8,
+            cf.below_average as u8,
+            cf.equal_average as u8,
+            number(cf.std_dev),
+            nullable(cf.icon_set.as_deref()),
+            cf.reverse as u8,
+            cf.hide_value as u8
+        ));
+        for (idx, formula) in cf.formulas.iter().enumerate() {
+            sql.push_str(&format!(
+                "INSERT INTO conditional_format_formula VALUES ({},{},{},{});\n",
+                quote(&cf.sheet),
+                cf.priority,
+                idx,
+                quote(formula)
+            ));
+        }
+        for (idx, threshold) in cf.thresholds.iter().enumerate() {
+            sql.push_str(&format!(
+                "INSERT INTO conditional_format_threshold VALUES ({},{},{},{},{},{});\n",
+                quote(&cf.sheet),
+                cf.priority,
+                idx,
+                quote(&threshold.value_type),
+                nullable(threshold.value.as_deref()),
+                threshold.greater_than as u8
+            ));
+        }
+        for (idx, color) in cf.colors.iter().enumerate() {
+            sql.push_str(&format!(
+                "INSERT INTO conditional_format_color VALUES ({},{},{},{});\n",
+                quote(&cf.sheet),
+                cf.priority,
+                idx,
+                quote(color)
+            ));
+        }
+    }
+
+    sql.push_str(&format!(
+        "CREATE TABLE differential_style ({});\n",
+        STYLE_COLUMNS
+    ));
+    for style in &wb.differential_styles {
+        sql.push_str(&format!(
+            "INSERT INTO differential_style VALUES ({});\n",
+            style_values(style).join(",")
+        ));
+    }
+
+    sql.push_str(
+        "CREATE TABLE drawing (sheet TEXT, kind TEXT, name TEXT, cell_range TEXT, x_offset INTEGER, y_offset INTEGER, width INTEGER, height INTEGER, \
+         alt_text TEXT, shape_type TEXT, text TEXT, media TEXT, media_type TEXT, data TEXT);\n",
+    );
+    for drawing in &wb.drawings {
+        sql.push_str(&format!(
+            "INSERT INTO drawing VALUES ({},'{}',{},{},{},{},{},{},{},{},{},{},{},{});\n",
+            quote(&drawing.sheet),
+            drawing.kind.as_str(),
+            nullable(drawing.name.as_deref()),
+            nullable(drawing.range.as_deref()),
+            drawing.x_offset,
+            drawing.y_offset,
+            number(drawing.width),
+            number(drawing.height),
+            nullable(drawing.alt_text.as_deref()),
+            nullable(drawing.shape_type.as_deref()),
+            nullable(drawing.text.as_deref()),
+            nullable(drawing.media.as_deref()),
+            nullable(drawing.media_type.as_deref()),
+            nullable(drawing.data.as_deref())
+        ));
+    }
+
+    // 系列は出現順の番号でグラフに紐付ける
+    sql.push_str(
+        "CREATE TABLE chart (chart_index INTEGER, sheet TEXT, name TEXT, cell_range TEXT, x_offset INTEGER, y_offset INTEGER, width INTEGER, height INTEGER, \
+         chart_type TEXT, bar_direction TEXT, grouping TEXT, style TEXT, title TEXT);\n",
+    );
+    sql.push_str(
+        "CREATE TABLE chart_series (chart_index INTEGER, series_index INTEGER, name TEXT, name_ref TEXT, category_range TEXT, value_range TEXT, chart_type TEXT);\n",
+    );
+    for (idx, chart) in wb.charts.iter().enumerate() {
+        sql.push_str(&format!(
+            "INSERT INTO chart VALUES ({},{},{},{},{},{},{},{},{},{},{},{},{});\n",
+            idx,
+            quote(&chart.sheet),
+            nullable(chart.name.as_deref()),
+            nullable(chart.range.as_deref()),
+            chart.x_offset,
+            chart.y_offset,
+            number(chart.width),
+            number(chart.height),
+            quote(&chart.chart_type),
+            nullable(chart.bar_direction.as_deref()),
+            nullable(chart.grouping.as_deref()),
+            nullable(chart.style.as_deref()),
+            nullable(chart.title.as_deref())
+        ));
+        for (series_idx, series) in chart.series.iter().enumerate() {
+            sql.push_str(&format!(
+                "INSERT INTO chart_series VALUES ({},{},{},{},{},{},{});\n",
+                idx,
+                series_idx,
+                nullable(series.name.as_deref()),
+                nullable(series.name_ref.as_deref()),
+                nullable(series.categories.as_deref()),
+                nullable(series.values.as_deref()),
+                nullable(series.chart_type.as_deref())
+            ));
+        }
+    }
+
+    // フィールドはシートとピボットテーブル名で紐付ける
+    sql.push_str(
+        "CREATE TABLE pivot_table (sheet TEXT, name TEXT, cell_range TEXT, source_type TEXT, source_sheet TEXT, source_range TEXT, source_name TEXT);\n",
+    );
+    sql.push_str(
+        "CREATE TABLE pivot_field (sheet TEXT, pivot_table TEXT, axis TEXT, field_index INTEGER, name TEXT);\n",
+    );
+    sql.push_str(
+        "CREATE TABLE pivot_data_field (sheet TEXT, pivot_table TEXT, field_index INTEGER, name TEXT, field TEXT, function TEXT);\n",
+    );
+    for pivot in &wb.pivot_tables {
+        sql.push_str(&format!(
+            "INSERT INTO pivot_table VALUES ({},{},{},{},{},{},{});\n",
+            quote(&pivot.sheet),
+            quote(&pivot.name),
+            quote(&pivot.range),
+            quote(&pivot.source_type),
+            nullable(pivot.source_sheet.as_deref()),
+            nullable(pivot.source_range.as_deref()),
+            nullable(pivot.source_name.as_deref())
+        ));
+        for (axis, fields) in [
+            ("row", &pivot.row_fields),
+            ("column", &pivot.column_fields),
+            ("page", &pivot.page_fields),
+        ] {
+            for (idx, field) in fields.iter().enumerate() {
+                sql.push_str(&format!(
+                    "INSERT INTO pivot_field VALUES ({},{},'{}',{},{});\n",
+                    quote(&pivot.sheet),
+                    quote(&pivot.name),
+                    axis,
+                    idx,
+                    quote(field)
+                ));
+            }
+        }
+        for (idx, field) in pivot.data_fields.iter().enumerate() {
+            sql.push_str(&format!(
+                "INSERT INTO pivot_data_field VALUES ({},{},{},{},{},{});\n",
+                quote(&pivot.sheet),
+                quote(&pivot.name),
+                idx,
+                nullable(field.name.as_deref()),
+                quote(&field.field),
+                quote(&field.function)
+            ));
+        }
+    }
+    sql
+}
+
+/// `cell_style` と `differential_style` に共通の列
+const STYLE_COLUMNS: &str = "id INTEGER, font_name TEXT, font_size REAL, bold INTEGER, italic INTEGER, underline TEXT, strike INTEGER, script TEXT, font_color TEXT, \
+     fill_pattern TEXT, fill_fg_color TEXT, fill_bg_color TEXT, \
+     border_left TEXT, border_left_color TEXT, border_right TEXT, border_right_color TEXT, border_top TEXT, border_top_color TEXT, \
+     border_bottom TEXT, border_bottom_color TEXT, border_diagonal TEXT, border_diagonal_color TEXT, diagonal_up INTEGER, diagonal_down INTEGER, \
+     horizontal TEXT, vertical TEXT, wrap_text INTEGER, shrink_to_fit INTEGER, indent INTEGER, text_rotation INTEGER, locked INTEGER, hidden INTEGER, \
+     number_format TEXT";
+
+/// `text_run` のフォントの列（`STYLE_COLUMNS` のフォント部分と同じ並び）
+const FONT_COLUMNS: &str = "font_name TEXT, font_size REAL, bold INTEGER, italic INTEGER, underline TEXT, strike INTEGER, script TEXT, font_color TEXT";
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn nullable(s: Option<&str>) -> String {
+    s.map(quote).unwrap_or_else(|| "NULL".into())
+}
+
+fn number<T: ToString>(n: Option<T>) -> String {
+    n.map(|n| n.to_string()).unwrap_or_else(|| "NULL".into())
+}
+
+/// 親要素（フォントなど）が無い場合の真偽値は NULL にする
+fn flag(b: Option<bool>) -> String {
+    number(b.map(u8::from))
+}
+
+/// `cell_style` の 1 行。入れ子の書式を列に平坦化する
+fn style_values(style: &CellStyle) -> Vec<String> {
+    let font = style.font.as_ref();
+    let fill = style.fill.as_ref();
+    let border = style.border.as_ref();
+    let alignment = style.alignment.as_ref();
+    let protection = style.protection.as_ref();
+
+    let mut values = vec![style.id.to_string()];
+    values.extend(font_values(font));
+    values.extend([
+        nullable(fill.map(|f| f.pattern.as_str())),
+        nullable(fill.and_then(|f| f.fg_color.as_deref())),
+        nullable(fill.and_then(|f| f.bg_color.as_deref())),
+    ]);
+    for edge in border_edges(border) {
+        values.push(nullable(edge.map(|e| e.style.as_str())));
+        values.push(nullable(edge.and_then(|e| e.color.as_deref())));
+    }
+    values.extend([
+        flag(border.map(|b| b.diagonal_up)),
+        flag(border.map(|b| b.diagonal_down)),
+        nullable(alignment.and_then(|a| a.horizontal.as_deref())),
+        nullable(alignment.and_then(|a| a.vertical.as_deref())),
+        flag(alignment.map(|a| a.wrap_text)),
+        flag(alignment.map(|a| a.shrink_to_fit)),
+        number(alignment.and_then(|a| a.indent)),
+        number(alignment.and_then(|a| a.text_rotation)),
+        flag(protection.map(|p| p.locked)),
+        flag(protection.map(|p| p.hidden)),
+        nullable(style.number_format.as_deref()),
+    ]);
+    values
+}
+
+fn font_values(font: Option<&FontStyle>) -> [String; 8] {
+    [
+        nullable(font.and_then(|f| f.name.as_deref())),
+        number(font.and_then(|f| f.size)),
+        flag(font.map(|f| f.bold)),
+        flag(font.map(|f| f.italic)),
+        nullable(font.and_then(|f| f.underline.as_deref())),
+        flag(font.map(|f| f.strike)),
+        nullable(font.and_then(|f| f.script.as_deref())),
+        nullable(font.and_then(|f| f.color.as_deref())),
+    ]
+}
+
+/// `workbook_protection` と `sheet_protection` に共通のパスワードの列。
+/// ハッシュの列は `include_password_hashes` 指定時のみ値を持つ
+const PASSWORD_COLUMNS: &str = "has_password INTEGER, password_algorithm TEXT, password_hash TEXT, \
+     password_salt TEXT, spin_count INTEGER";
+
+fn password_values(has_password: bool, password: Option<&PasswordHash>) -> [String; 5] {
+    [
+        (has_password as u8).to_string(),
+        nullable(password.and_then(|p| p.algorithm.as_deref())),
+        nullable(password.map(|p| p.hash.as_str())),
+        nullable(password.and_then(|p| p.salt.as_deref())),
+        number(password.and_then(|p| p.spin_count)),
+    ]
+}
+
+fn border_edges(border: Option<&BorderStyle>) -> [Option<&BorderEdge>; 5] {
+    match border {
+        Some(b) => [
+            b.left.as_ref(),
+            b.right.as_ref(),
+            b.top.as_ref(),
+            b.bottom.as_ref(),
+            b.diagonal.as_ref(),
+        ],
+        None => [None; 5],
+    }
+}
+
+/// `style_values` の逆変換。グループ内の列がすべて NULL ならそのグループは無しとする
+fn style_from_row(row: &mut Row) -> Result<CellStyle, String> {
+    let id = row.number(0)?;
+
+    let font = font_from_row(row, 1)?;
+
+    let pattern = row.nullable_text(9)?;
+    let (fg_color, bg_color) = (row.nullable_text(10)?, row.nullable_text(11)?);
+    let fill = pattern.map(|pattern| FillStyle {
+        pattern,
+        fg_color,
+        bg_color,
+    });
+
+    let mut edges = Vec::new();
+    for i in 0..5 {
+        let style = row.nullable_text(12 + i * 2)?;
+        let color = row.nullable_text(13 + i * 2)?;
+        edges.push(style.map(|style| BorderEdge { style, color }));
+    }
+    let diagonal_up = row.optional_flag(22)?;
+    let diagonal_down = row.optional_flag(23)?;
+    let mut edges = edges.into_iter();
+    let border = BorderStyle {
+        left: edges.next().flatten(),
+        right: edges.next().flatten(),
+        top: edges.next().flatten(),
+        bottom: edges.next().flatten(),
+        diagonal: edges.next().flatten(),
+        diagonal_up: diagonal_up.unwrap_or(false),
+        diagonal_down: diagonal_down.unwrap_or(false),
+    };
+
+    let has_alignment = (24..=29).any(|i| !row.is_null(i));
+    let alignment = AlignmentStyle {
+        horizontal: row.nullable_text(24)?,
+        vertical: row.nullable_text(25)?,
+        wrap_text: row.optional_flag(26)?.unwrap_or(false),
+        shrink_to_fit: row.optional_flag(27)?.unwrap_or(false),
+        indent: row.optional_number(28)?,
+        text_rotation: row.optional_number(29)?,
+    };
+
+    let locked = row.optional_flag(30)?;
+    let hidden = row.optional_flag(31)?;
+    let protection = (locked.is_some() || hidden.is_some()).then(|| ProtectionStyle {
+        locked: locked.unwrap_or(true),
+        hidden: hidden.unwrap_or(false),
+    });
+
+    Ok(CellStyle {
+        id,
+        font,
+        fill,
+        border: (border != BorderStyle::default()).then_some(border),
+        alignment: has_alignment.then_some(alignment),
+        protection,
+        number_format: row.optional(32)?,
+    })
+}
+
+/// `font_values` の逆変換。`first` 列から 8 列がすべて NULL ならフォント無し
+fn font_from_row(row: &mut Row, first: usize) -> Result<Option<FontStyle>, String> {
+    if (first..first + 8).all(|i| row.is_null(i)) {
+        return Ok(None);
+    }
+    Ok(Some(FontStyle {
+        name: row.nullable_text(first)?,
+        size: row.optional_number(first + 1)?,
+        bold: row.optional_flag(first + 2)?.unwrap_or(false),
+        italic: row.optional_flag(first + 3)?.unwrap_or(false),
+        underline: row.nullable_text(first + 4)?,
+        strike: row.optional_flag(first + 5)?.unwrap_or(false),
+        script: row.nullable_text(first + 6)?,
+        color: row.nullable_text(first + 7)?,
+    }))
+}
+
+/// `has_password` とハッシュ
+fn password_from_row(row: &mut Row, first: usize) -> Result<(bool, Option<PasswordHash>), String> {
+    let has_password = row.number::<u8>(first)? != 0;
+    let Some(hash) = row.nullable_text(first + 2)? else {
+        return Ok((has_password, None));
+    };
+    let password = PasswordHash {
+        algorithm: row.nullable_text(first + 1)?,
+        hash,
+        salt: row.nullable_text(first + 3)?,
+        spin_count: row.optional_number(first + 4)?,
+    };
+    Ok((has_password, Some(password)))
+}
+
+/// 先頭 2 列（シート・セル番地）が指すセル
+fn addressed_cell<'a>(wb: &'a mut Workbook, row: &mut Row) -> Result<&'a mut CellData, String> {
+    let (sheet, address) = (row.text(0)?, row.text(1)?);
+    wb.cells
+        .iter_mut()
+        .rev()
+        .find(|c| c.sheet == sheet && c.address == address)
+        .ok_or_else(|| format!("Unknown cell {}!{}", sheet, address))
+}
+
+/// 先頭列のシート名が指すシート
+fn named_sheet<'a>(wb: &'a mut Workbook, row: &mut Row) -> Result<&'a mut SheetMetadata, String> {
+    let name = row.text(0)?;
+    wb.sheets
+        .iter_mut()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Unknown sheet '{}'", name))
+}
+
+/// 先頭 2 列（シート・優先順位）が指す条件付き書式
+fn conditional_rule<'a>(
+    wb: &'a mut Workbook,
+    row: &mut Row,
+) -> Result<&'a mut ConditionalFormat, String> {
+    let (sheet, priority): (String, u32) = (row.text(0)?, row.number(1)?);
+    wb.conditional_formats
+        .iter_mut()
+        .rev()
+        .find(|c| c.sheet == sheet && c.priority == priority)
+        .ok_or_else(|| {
+            format!(
+                "Unknown conditional format '{}' priority {}",
+                sheet, priority
+            )
+        })
+}
+
+/// 先頭 2 列（シート・名前）が指すピボットテーブル
+fn pivot_table<'a>(wb: &'a mut Workbook, row: &mut Row) -> Result<&'a mut PivotTable, String> {
+    let (sheet, name) = (row.text(0)?, row.text(1)?);
+    wb.pivot_tables
+        .iter_mut()
+        .find(|p| p.sheet == sheet && p.name == name)
+        .ok_or_else(|| format!("Unknown pivot table {}!{}", sheet, name))
+}
+
+/// `to_sql` が出力した INSERT 文を読み戻して Workbook を再構成する
+pub fn from_sql(sql: &str) -> Result<Workbook, String> {
+    let mut wb = Workbook::default();
+
+    for (table, values) in parse_inserts(sql)? {
+        let mut row = Row {
+            table: &table,
+            values,
+        };
+        match table.as_str() {
+            "workbook" => {
+                wb.source_format = row
+                    .nullable_text(0)?
+                    .map(|s| {
+                        SourceFormat::from_name(&s)
+                            .ok_or_else(|| format!("Invalid value '{}' in workbook", s))
+                    })
+                    .transpose()?;
+                wb.date1904 = row.optional_flag(1)?;
+            }
+            "sheet_metadata" => {
+                let mut sheet = SheetMetadata {
+                    name: row.text(0)?,
+                    index: row.number(1)?,
+                    hidden: row.number::<u8>(2)? != 0,
+                    visibility: row.parse_with(3, SheetVisibility::from_name)?,
+                    sheet_type: row.parse_with(4, SheetKind::from_name)?,
+                    tab_color: row.optional(5)?,
+                    zoom: row.optional_number(6)?,
+                    hide_gridlines: row.optional_flag(7)?.unwrap_or(false),
+                    default_row_height: row.optional_number(8)?,
+                    default_col_width: row.optional_number(9)?,
+                    ..Default::default()
+                };
+                if let Some(rows) = row.optional_number(10)? {
+                    sheet.freeze_panes = Some(FreezePanes {
+                        rows,
+                        cols: row.optional_number(11)?.unwrap_or(0),
+                        top_left_cell: row.optional(12)?,
+                    });
+                }
+                wb.sheets.push(sheet);
+            }
+            "column_layout" => {
+                let sheet = named_sheet(&mut wb, &mut row)?;
+                sheet.columns.push(ColumnLayout {
+                    first_col: row.number(1)?,
+                    last_col: row.number(2)?,
+                    width: row.optional_number(3)?,
+                    hidden: row.number::<u8>(4)? != 0,
+                    outline_level: row.number(5)?,
+                    collapsed: row.number::<u8>(6)? != 0,
+                });
+            }
+            "print_setup" => {
+                let sheet = named_sheet(&mut wb, &mut row)?;
+                let mut setup = PrintSetup {
+                    print_area: row.nullable_text(1)?,
+                    repeat_rows: row.nullable_text(2)?,
+                    repeat_cols: row.nullable_text(3)?,
+                    orientation: row.nullable_text(4)?,
+                    paper_size: row.optional_number(5)?,
+                    scale: row.optional_number(6)?,
+                    fit_to_width: row.optional_number(7)?,
+                    fit_to_height: row.optional_number(8)?,
+                    header: row.nullable_text(15)?,
+                    footer: row.nullable_text(16)?,
+                    ..Default::default()
+                };
+                if !row.is_null(9) {
+                    setup.margins = Some(PageMargins {
+                        left: row.number(9)?,
+                        right: row.number(10)?,
+                        top: row.number(11)?,
+                        bottom: row.number(12)?,
+                        header: row.number(13)?,
+                        footer: row.number(14)?,
+                    });
+                }
+                sheet.print_setup = Some(setup);
+            }
+            "page_break" => {
+                let sheet = named_sheet(&mut wb, &mut row)?;
+                let setup = sheet.print_setup.get_or_insert_with(Default::default);
+                let position = row.number(2)?;
+                match row.text(1)?.as_str() {
+                    "row" => setup.row_breaks.push(position),
+                    "col" => setup.col_breaks.push(position),
+                    other => return Err(format!("Invalid value '{}' in page_break", other)),
+                }
+            }
+            "row_layout" => {
+                let sheet = named_sheet(&mut wb, &mut row)?;
+                sheet.rows.push(RowLayout {
+                    row: row.number(1)?,
+                    height: row.optional_number(2)?,
+                    hidden: row.number::<u8>(3)? != 0,
+                    outline_level: row.number(4)?,
+                    collapsed: row.number::<u8>(5)? != 0,
+                });
+            }
+            "workbook_protection" => {
+                let (has_password, password) = password_from_row(&mut row, 2)?;
+                wb.protection = Some(WorkbookProtection {
+                    structure: row.number::<u8>(0)? != 0,
+                    windows: row.number::<u8>(1)? != 0,
+                    has_password,
+                    password,
+                });
+            }
+            "sheet_protection" => {
+                let (has_password, password) = password_from_row(&mut row, 1)?;
+                let mut allowed = [false; 15];
+                for (i, flag) in allowed.iter_mut().enumerate() {
+                    *flag = row.number::<u8>(6 + i)? != 0;
+                }
+                let [
+                    select_locked_cells,
+                    select_unlocked_cells,
+                    format_cells,
+                    format_columns,
+                    format_rows,
+                    insert_columns,
+                    insert_rows,
+                    insert_hyperlinks,
+                    delete_columns,
+                    delete_rows,
+                    sort,
+                    auto_filter,
+                    pivot_tables,
+                    edit_objects,
+                    edit_scenarios,
+                ] = allowed;
+                named_sheet(&mut wb, &mut row)?.protection = Some(SheetProtection {
+                    has_password,
+                    password,
+                    select_locked_cells,
+                    select_unlocked_cells,
+                    format_cells,
+                    format_columns,
+                    format_rows,
+                    insert_columns,
+                    insert_rows,
+                    insert_hyperlinks,
+                    delete_columns,
+                    delete_rows,
+                    sort,
+                    auto_filter,
+                    pivot_tables,
+                    edit_objects,
+                    edit_scenarios,
+                });
+            }
+            "cell_data" => wb.cells.push(CellData {
+                sheet: row.text(0)?,
+                address: row.text(1)?,
+                row: row.number(2)?,
+                col: row.number(3)?,
+                data_type: row.parse_with(4, CellType::from_name)?,
+                value: row.text(5)?,
+                formula: row.nullable_text(6)?,
+                serial: row.optional_number(7)?,
+                number_format: row.optional(8)?,
+                display_text: row.optional(9)?,
+                style_id: row.optional_number(10)?,
+                runs: Vec::new(),
+                phonetic: Vec::new(),
+                pivot_table: row.optional(11)?,
+                locked: row.optional_flag(12)?.unwrap_or(true),
+            }),
+            "text_run" => {
+                let text = row.text(3)?;
+                let font = font_from_row(&mut row, 4)?;
+                addressed_cell(&mut wb, &mut row)?
+                    .runs
+                    .push(TextRun { text, font });
+            }
+            "phonetic_run" => {
+                let phonetic = PhoneticRun {
+                    start: row.number(2)?,
+                    end: row.number(3)?,
+                    text: row.text(4)?,
+                };
+                addressed_cell(&mut wb, &mut row)?.phonetic.push(phonetic);
+            }
+            "cell_style" => wb.styles.push(style_from_row(&mut row)?),
+            "merged_range" => wb.merged_ranges.push(MergedRange {
+                sheet: row.text(0)?,
+                start: row.text(1)?,
+                end: row.text(2)?,
+            }),
+            "comment" => {
+                let (sheet, address) = (row.text(0)?, row.text(1)?);
+                let reply_index: usize = row.number(2)?;
+                let (author, text) = (row.nullable_text(3)?, row.text(4)?);
+                if reply_index == 0 {
+                    wb.comments.push(Comment {
+                        sheet,
+                        address,
+                        author,
+                        text,
+                        replies: Vec::new(),
+                    });
+                } else {
+                    let parent = wb
+                        .comments
+                        .iter_mut()
+                        .rev()
+                        .find(|c| c.sheet == sheet && c.address == address)
+                        .ok_or_else(|| format!("Reply without comment at {}!{}", sheet, address))?;
+                    parent.replies.push(CommentReply { author, text });
+                }
+            }
+            "hyperlink" => wb.hyperlinks.push(Hyperlink {
+                sheet: row.text(0)?,
+                range: row.text(1)?,
+                target: row.text(2)?,
+                tooltip: row.nullable_text(3)?,
+                internal: row.number::<u8>(4)? != 0,
+            }),
+            "defined_name" => wb.defined_names.push(DefinedName {
+                name: row.text(0)?,
+                scope: row.nullable_text(1)?,
+                refers_to: row.text(2)?,
+                hidden: row.number::<u8>(3)? != 0,
+            }),
+            "excel_table" => wb.tables.push(Table {
+                name: row.text(0)?,
+                sheet: row.text(1)?,
+                range: row.text(2)?,
+                header_row: row.number::<u8>(3)? != 0,
+                totals_row: row.number::<u8>(4)? != 0,
+                columns: Vec::new(),
+                style: row.nullable_text(5)?,
+            }),
+            "table_column" => {
+                let table_name = row.text(0)?;
+                let table = wb
+                    .tables
+                    .iter_mut()
+                    .find(|t| t.name == table_name)
+                    .ok_or_else(|| format!("Column of unknown table '{}'", table_name))?;
+                table.columns.push(row.text(2)?);
+            }
+            "data_validation" => wb.data_validations.push(DataValidation {
+                sheet: row.text(0)?,
+                range: row.text(1)?,
+                validation_type: row.text(2)?,
+                operator: row.nullable_text(3)?,
+                formula1: row.nullable_text(4)?,
+                formula2: row.nullable_text(5)?,
+                allow_blank: row.number::<u8>(6)? != 0,
+                show_dropdown: row.number::<u8>(7)? != 0,
+                show_input_message: row.number::<u8>(8)? != 0,
+                show_error_message: row.number::<u8>(9)? != 0,
+                error_style: row.nullable_text(10)?,
+                error_title: row.nullable_text(11)?,
+                error_message: row.nullable_text(12)?,
+                prompt_title: row.nullable_text(13)?,
+                prompt_message: row.nullable_text(14)?,
+            }),
+            "conditional_format" => wb.conditional_formats.push(ConditionalFormat {
+                sheet: row.text(0)?,
+                range: row.text(1)?,
+                priority: row.number(2)?,
+                rule_type: row.text(3)?,
+                stop_if_true: row.number::<u8>(4)? != 0,
+                style_id: row.optional_number(5)?,
+                operator: row.nullable_text(6)?,
+                text: row.nullable_text(7)?,
+                time_period: row.nullable_text(8)?,
+                rank: row.optional_number(9)?,
+                percent: row.number::<u8>(10)? != 0,
+                bottom: row.number::<u8>(11)? != 0,
+                below_average: row.number::<u8>(12)? != 0,
+                equal_average: row.number::<u8>(13)? != 0,
+                std_dev: row.optional_number(14)?,
+                icon_set: row.nullable_text(15)?,
+                reverse: row.number::<u8>(16)? != 0,
+                hide_value: row.number::<u8>(17)? != 0,
+                formulas: Vec::new(),
+                thresholds: Vec::new(),
+                colors: Vec::new(),
+            }),
+            "conditional_format_formula" => {
+                let rule = conditional_rule(&mut wb, &mut row)?;
+                rule.formulas.push(row.text(3)?);
+            }
+            "conditional_format_threshold" => {
+                let rule = conditional_rule(&mut wb, &mut row)?;
+                rule.thresholds.push(ConditionalThreshold {
+                    value_type: row.text(3)?,
+                    value: row.nullable_text(4)?,
+                    greater_than: row.number::<u8>(5)? != 0,
+                });
+            }
+            "conditional_format_color" => {
+                let rule = conditional_rule(&mut wb, &mut row)?;
+                rule.colors.push(row.text(3)?);
+            }
+            "differential_style" => wb.differential_styles.push(style_from_row(&mut row)?),
+            "drawing" => wb.drawings.push(Drawing {
+                sheet: row.text(0)?,
+                kind: row.parse_with(1, DrawingKind::from_name)?,
+                name: row.nullable_text(2)?,
+                range: row.nullable_text(3)?,
+                x_offset: row.number(4)?,
+                y_offset: row.number(5)?,
+                width: row.optional_number(6)?,
+                height: row.optional_number(7)?,
+                alt_text: row.nullable_text(8)?,
+                shape_type: row.nullable_text(9)?,
+                text: row.nullable_text(10)?,
+                media: row.nullable_text(11)?,
+                media_type: row.nullable_text(12)?,
+                data: row.nullable_text(13)?,
+            }),
+            "chart" => wb.charts.push(Chart {
+                sheet: row.text(1)?,
+                name: row.nullable_text(2)?,
+                range: row.nullable_text(3)?,
+                x_offset: row.number(4)?,
+                y_offset: row.number(5)?,
+                width: row.optional_number(6)?,
+                height: row.optional_number(7)?,
+                chart_type: row.text(8)?,
+                bar_direction: row.nullable_text(9)?,
+                grouping: row.nullable_text(10)?,
+                style: row.nullable_text(11)?,
+                title: row.nullable_text(12)?,
+                series: Vec::new(),
+            }),
+            "chart_series" => {
+                let idx: usize = row.number(0)?;
+                let chart = wb
+                    .charts
+                    .get_mut(idx)
+                    .ok_or_else(|| format!("Series of unknown chart {}", idx))?;
+                chart.series.push(ChartSeries {
+                    name: row.nullable_text(2)?,
+                    name_ref: row.nullable_text(3)?,
+                    categories: row.nullable_text(4)?,
+                    values: row.nullable_text(5)?,
+                    chart_type: row.nullable_text(6)?,
+                });
+            }
+            "pivot_table" => wb.pivot_tables.push(PivotTable {
+                sheet: row.text(0)?,
+                name: row.text(1)?,
+                range: row.text(2)?,
+                source_type: row.text(3)?,
+                source_sheet: row.nullable_text(4)?,
+                source_range: row.nullable_text(5)?,
+                source_name: row.nullable_text(6)?,
+                row_fields: Vec::new(),
+                column_fields: Vec::new(),
+                page_fields: Vec::new(),
+                data_fields: Vec::new(),
+            }),
+            "pivot_field" => {
+                let (axis, name) = (row.text(2)?, row.text(4)?);
+                let pivot = pivot_table(&mut wb, &mut row)?;
+                let fields = match axis.as_str() {
+                    "row" => &mut pivot.row_fields,
+                    "column" => &mut pivot.column_fields,
+                    "page" => &mut pivot.page_fields,
+                    _ => return Err(format!("Invalid value '{}' in pivot_field", axis)),
+                };
+                fields.push(name);
+            }
+            "pivot_data_field" => {
+                let field = PivotDataField {
+                    name: row.nullable_text(3)?,
+                    field: row.text(4)?,
+                    function: row.text(5)?,
+                };
+                pivot_table(&mut wb, &mut row)?.data_fields.push(field);
+            }
+            _ => return Err(format!("Unknown table: {}", table)),
+        }
+    }
+
+    Ok(wb)
+}
+
+enum SqlValue {
+    Null,
+    Text(String),
+    Literal(String),
+}
+
+struct Row<'a> {
+    table: &'a str,
+    values: Vec<SqlValue>,
+}
+
+impl Row<'_> {
+    fn take(&mut self, idx: usize) -> Result<SqlValue, String> {
+        self.values
+            .get_mut(idx)
+            .map(|v| std::mem::replace(v, SqlValue::Null))
+            .ok_or_else(|| format!("Missing column {} in {}", idx + 1, self.table))
+    }
+
+    fn nullable_text(&mut self, idx: usize) -> Result<Option<String>, String> {
+        match self.take(idx)? {
+            SqlValue::Null => Ok(None),
+            SqlValue::Text(s) | SqlValue::Literal(s) => Ok(Some(s)),
+        }
+    }
+
+    /// 後から追加された列。旧形式の出力には無いので、欠落していれば NULL とみなす
+    fn optional(&mut self, idx: usize) -> Result<Option<String>, String> {
+        if idx >= self.values.len() {
+            return Ok(None);
+        }
+        self.nullable_text(idx)
+    }
+
+    fn optional_number<T: std::str::FromStr>(&mut self, idx: usize) -> Result<Option<T>, String> {
+        self.optional(idx)?
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| format!("Invalid number '{}' in {}", s, self.table))
+            })
+            .transpose()
+    }
+
+    fn optional_flag(&mut self, idx: usize) -> Result<Option<bool>, String> {
+        Ok(self.optional_number::<u8>(idx)?.map(|n| n != 0))
+    }
+
+    fn is_null(&self, idx: usize) -> bool {
+        matches!(self.values.get(idx), None | Some(SqlValue::Null))
+    }
+
+    fn text(&mut self, idx: usize) -> Result<String, String> {
+        self.nullable_text(idx)?
+            .ok_or_else(|| format!("Unexpected NULL in column {} of {}", idx + 1, self.table))
+    }
+
+    fn parse_with<T>(&mut self, idx: usize, f: impl Fn(&str) -> Option<T>) -> Result<T, String> {
+        let s = self.text(idx)?;
+        f(&s).ok_or_else(|| format!("Invalid value '{}' in {}", s, self.table))
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, idx: usize) -> Result<T, String> {
+        let s = self.text(idx)?;
+        s.parse()
+            .map_err(|_| format!("Invalid number '{}' in {}", s, self.table))
+    }
+}
+
+fn parse_inserts(sql: &str) -> Result<Vec<(String, Vec<SqlValue>)>, String> {
+    let mut statements = Vec::new();
+    let mut chars = sql.chars().peekable();
+
+    loop {
+        let keyword = read_word(&mut chars);
+        if keyword.is_empty() {
+            if chars.peek().is_none() {
+                break;
+            }
+            return Err(format!("Unexpected character: {:?}", chars.peek()));
+        }
+
+        if keyword.eq_ignore_ascii_case("CREATE") {
+            skip_statement(&mut chars);
+            continue;
+        }
+        if !keyword.eq_ignore_ascii_case("INSERT")
+            || !read_word(&mut chars).eq_ignore_ascii_case("INTO")
+        {
+            return Err(format!("Unsupported statement: {}", keyword));
+        }
+        let table = read_word(&mut chars);
+        if !read_word(&mut chars).eq_ignore_ascii_case("VALUES") {
+            return Err(format!("Expected VALUES after INSERT INTO {}", table));
+        }
+        skip_whitespace(&mut chars);
+        if chars.next() != Some('(') {
+            return Err(format!("Expected '(' in INSERT INTO {}", table));
+        }
+
+        let mut values = Vec::new();
+        loop {
+            skip_whitespace(&mut chars);
+            match chars.peek() {
+                Some('\'') => {
+                    chars.next();
+                    let mut s = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('\'') if chars.peek() == Some(&'\'') => {
+                                chars.next();
+                                s.push('\'');
+                            }
+                            Some('\'') => break,
+                            Some(c) => s.push(c),
+                            None => return Err("Unterminated string literal".into()),
+                        }
+                    }
+                    values.push(SqlValue::Text(s));
+                }
+                Some(_) => {
+                    let mut s = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c == ',' || c == ')' || c.is_whitespace() {
+                            break;
+                        }
+                        s.push(c);
+                        chars.next();
+                    }
+                    if s.eq_ignore_ascii_case("NULL") {
+                        values.push(SqlValue::Null);
+                    } else {
+                        values.push(SqlValue::Literal(s));
+                    }
+                }
+                None => return Err("Unexpected end of input".into()),
+            }
+
+            skip_whitespace(&mut chars);
+            match chars.next() {
+                Some(',') => continue,
+                Some(')') => break,
+                other => return Err(format!("Unexpected token in VALUES: {:?}", other)),
+            }
+        }
+        skip_whitespace(&mut chars);
+        if chars.peek() == Some(&';') {
+            chars.next();
+        }
+
+        statements.push((table, values));
+    }
+
+    Ok(statements)
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn read_word(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    skip_whitespace(chars);
+    let mut word = String::new();
+    while let Some(&c) = chars.peek() {
+        if !(c.is_alphanumeric() || c == '_') {
+            break;
+        }
+        word.push(c);
+        chars.next();
+    }
+    word
+}
+
+fn skip_statement(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    let mut in_string = false;
+    for c in chars.by_ref() {
+        match c {
+            '\'' => in_string = !in_string,
+            ';' if !in_string => break,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use crate::model::{
+    AlignmentStyle, BorderEdge, BorderStyle, CellStyle, FillStyle, FontStyle, ProtectionStyle,
+};
+use crate::ooxml::{Element, Package};
+
+/// 旧形式のインデックスカラー（0〜63）
+const INDEXED_COLORS: [u32; 64] = [
+    0x000000, 0xFFFFFF, 0xFF0000, 0x00FF00, 0x0000FF, 0xFFFF00, 0xFF00FF, 0x00FFFF, //
+    0x000000, 0xFFFFFF, 0xFF0000, 0x00FF00, 0x0000FF, 0xFFFF00, 0xFF00FF, 0x00FFFF, //
+    0x800000, 0x008000, 0x000080, 0x808000, 0x800080, 0x008080, 0xC0C0C0, 0x808080, //
+    0x9999FF, 0x993366, 0xFFFFCC, 0xCCFFFF, 0x660066, 0xFF8080, 0x0066CC, 0xCCCCFF, //
+    0x000080, 0xFF00FF, 0xFFFF00, 0x00FFFF, 0x800080, 0x800000, 0x008080, 0x0000FF, //
+    0x00CCFF, 0xCCFFFF, 0xCCFFCC, 0xFFFF99, 0x99CCFF, 0xFF99CC, 0xCC99FF, 0xFFCC99, //
+    0x3366FF, 0x33CCCC, 0x99CC00, 0xFFCC00, 0xFF9900, 0xFF6600, 0x666699, 0x969696, //
+    0x003366, 0x339966, 0x003300, 0x333300, 0x993300, 0x993366, 0x333399, 0x333333, //
+];
+
+/// テーマ色の並び。`theme` 属性の 0〜3 は背景・文字の順（lt1, dk1, lt2, dk2）になる
+const THEME_SLOTS: [&str; 12] = [
+    "lt1", "dk1", "lt2", "dk2", "accent1", "accent2", "accent3", "accent4", "accent5", "accent6",
+    "hlink", "folHlink",
+];
+
+/// `cellXfs` の並び順に、各セル書式を解決して返す（`id` は未設定）
+pub fn read_cell_styles(package: &mut Package) -> Vec<CellStyle> {
+    let Some(styles) = package.part("xl/styles.xml") else {
+        return Vec::new();
+    };
+    let colors = Colors::read(package);
+
+    let list = |name: &str, item: &'static str| -> Vec<&Element> {
+        styles
+            .child(name)
+            .into_iter()
+            .flat_map(|l| l.children_named(item))
+            .collect()
+    };
+    let fonts: Vec<FontStyle> = list("fonts", "font")
+        .into_iter()
+        .map(|f| read_font(f, &colors))
+        .collect();
+    let fills: Vec<Option<FillStyle>> = list("fills", "fill")
+        .into_iter()
+        .map(|f| read_fill(f, &colors, "none"))
+        .collect();
+    let borders: Vec<Option<BorderStyle>> = list("borders", "border")
+        .into_iter()
+        .map(|b| read_border(b, &colors))
+        .collect();
+
+    let index = |xf: &Element, name: &str| -> Option<usize> { xf.attr(name)?.parse().ok() };
+    list("cellXfs", "xf")
+        .into_iter()
+        .map(|xf| CellStyle {
+            id: 0,
+            font: index(xf, "fontId").and_then(|i| fonts.get(i).cloned()),
+            fill: index(xf, "fillId").and_then(|i| fills.get(i).cloned().flatten()),
+            border: index(xf, "borderId").and_then(|i| borders.get(i).cloned().flatten()),
+            alignment: xf.child("alignment").and_then(read_alignment),
+            protection: xf.child("protection").and_then(read_protection),
+            number_format: None,
+        })
+        .collect()
+}
+
+/// `cellXfs` の並び順に、セル書式の保護設定からロックの有無だけを読む
+pub fn read_cell_locks(package: &mut Package) -> Vec<bool> {
+    let Some(styles) = package.part("xl/styles.xml") else {
+        return Vec::new();
+    };
+    styles
+        .child("cellXfs")
+        .into_iter()
+        .flat_map(|l| l.children_named("xf"))
+        .map(|xf| {
+            xf.child("protection")
+                .and_then(read_protection)
+                .is_none_or(|p| p.locked)
+        })
+        .collect()
+}
+
+/// 条件付き書式が参照する差分書式（`dxfs`）を並び順に読む。`id` は位置と一致する
+pub fn read_differential_styles(package: &mut Package) -> Vec<CellStyle> {
+    let Some(styles) = package.part("xl/styles.xml") else {
+        return Vec::new();
+    };
+    let colors = Colors::read(package);
+    styles
+        .child("dxfs")
+        .into_iter()
+        .flat_map(|d| d.children_named("dxf"))
+        .enumerate()
+        .map(|(id, dxf)| CellStyle {
+            id,
+            font: dxf.child("font").map(|f| read_font(f, &colors)),
+            // 差分書式では patternType を省略すると単色塗りになる
+            fill: dxf
+                .child("fill")
+                .and_then(|f| read_fill(f, &colors, "solid")),
+            border: dxf.child("border").and_then(|b| read_border(b, &colors)),
+            alignment: dxf.child("alignment").and_then(read_alignment),
+            protection: dxf.child("protection").and_then(read_protection),
+            number_format: dxf
+                .child("numFmt")
+                .and_then(|n| n.attr("formatCode"))
+                .map(ToString::to_string),
+        })
+        .collect()
+}
+
+/// `<b/>` や `<b val="1"/>` のような真偽値要素
+fn flag(parent: &Element, name: &str) -> bool {
+    parent
+        .child(name)
+        .is_some_and(|e| !matches!(e.attr("val"), Some("0" | "false")))
+}
+
+/// `<font>` または書式付き文字列の区間の `<rPr>`（フォント名は `rFont`）を読む
+pub fn read_font(font: &Element, colors: &Colors) -> FontStyle {
+    let val = |name: &str| font.child(name).and_then(|e| e.attr("val"));
+    FontStyle {
+        name: val("name")
+            .or_else(|| val("rFont"))
+            .map(ToString::to_string),
+        size: val("sz").and_then(|s| s.parse().ok()),
+        bold: flag(font, "b"),
+        italic: flag(font, "i"),
+        underline: font
+            .child("u")
+            .map(|u| u.attr("val").unwrap_or("single").to_string())
+            .filter(|u| u != "none"),
+        strike: flag(font, "strike"),
+        script: val("vertAlign")
+            .filter(|v| *v != "baseline")
+            .map(ToString::to_string),
+        color: font.child("color").and_then(|c| colors.resolve(c)),
+    }
+}
+
+fn read_fill(fill: &Element, colors: &Colors, default_pattern: &str) -> Option<FillStyle> {
+    let pattern = fill.child("patternFill")?;
+    let pattern_type = pattern.attr("patternType").unwrap_or(default_pattern);
+    if pattern_type == "none" {
+        return None;
+    }
+    Some(FillStyle {
+        pattern: pattern_type.to_string(),
+        fg_color: pattern.child("fgColor").and_then(|c| colors.resolve(c)),
+        bg_color: pattern.child("bgColor").and_then(|c| colors.resolve(c)),
+    })
+}
+
+fn read_border(border: &Element, colors: &Colors) -> Option<BorderStyle> {
+    let edge = |name: &str| -> Option<BorderEdge> {
+        let e = border.child(name)?;
+        Some(BorderEdge {
+            style: e.attr("style").filter(|s| *s != "none")?.to_string(),
+            color: e.child("color").and_then(|c| colors.resolve(c)),
+        })
+    };
+    let style = BorderStyle {
+        left: edge("left").or_else(|| edge("start")),
+        right: edge("right").or_else(|| edge("end")),
+        top: edge("top"),
+        bottom: edge("bottom"),
+        diagonal: edge("diagonal"),
+        diagonal_up: matches!(border.attr("diagonalUp"), Some("1" | "true")),
+        diagonal_down: matches!(border.attr("diagonalDown"), Some("1" | "true")),
+    };
+    (style != BorderStyle::default()).then_some(style)
+}
+
+fn read_alignment(alignment: &Element) -> Option<AlignmentStyle> {
+    let attr = |name: &str| alignment.attr(name);
+    let style = AlignmentStyle {
+        horizontal: attr("horizontal")
+            .filter(|h| *h != "general")
+            .map(ToString::to_string),
+        vertical: attr("vertical")
+            .filter(|v| *v != "bottom")
+            .map(ToString::to_string),
+        wrap_text: matches!(attr("wrapText"), Some("1" | "true")),
+        shrink_to_fit: matches!(attr("shrinkToFit"), Some("1" | "true")),
+        indent: attr("indent")
+            .and_then(|i| i.parse().ok())
+            .filter(|i| *i > 0),
+        text_rotation: attr("textRotation")
+            .and_then(|r| r.parse().ok())
+            .filter(|r| *r > 0),
+    };
+    (style != AlignmentStyle::default()).then_some(style)
+}
+
+fn read_protection(protection: &Element) -> Option<ProtectionStyle> {
+    let style = ProtectionStyle {
+        locked: !matches!(protection.attr("locked"), Some("0" | "false")),
+        hidden: matches!(protection.attr("hidden"), Some("1" | "true")),
+    };
+    (!style.locked || style.hidden).then_some(style)
+}
+
+fn theme_colors(theme: &Element) -> Vec<u32> {
+    let Some(scheme) = theme
+        .child("themeElements")
+        .and_then(|e| e.child("clrScheme"))
+    else {
+        return Vec::new();
+    };
+    THEME_SLOTS
+        .iter()
+        .map(|slot| {
+            scheme
+                .child(slot)
+                .and_then(|c| c.children.first())
+                .and_then(|c| {
+                    c.attr("val")
+                        .filter(|_| c.name == "srgbClr")
+                        .or(c.attr("lastClr"))
+                })
+                .and_then(|v| u32::from_str_radix(v, 16).ok())
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+#[derive(Default)]
+pub struct Colors {
+    theme: Vec<u32>,
+}
+
+impl Colors {
+    /// テーマ色をパッケージから読み込む
+    pub fn read(package: &mut Package) -> Colors {
+        let theme = package
+            .part("xl/theme/theme1.xml")
+            .map(|t| theme_colors(&t))
+            .unwrap_or_default();
+        Colors { theme }
+    }
+
+    /// `rgb` / `theme`（+ `tint`）/ `indexed` を `#RRGGBB` に解決する。自動色は `None`
+    pub fn resolve(&self, color: &Element) -> Option<String> {
+        let rgb = if let Some(argb) = color.attr("rgb") {
+            u32::from_str_radix(argb, 16).ok()? & 0xFF_FFFF
+        } else if let Some(theme) = color.attr("theme") {
+            *self.theme.get(theme.parse::<usize>().ok()?)?
+        } else if let Some(indexed) = color.attr("indexed") {
+            *INDEXED_COLORS.get(indexed.parse::<usize>().ok()?)?
+        } else {
+            return None;
+        };
+        let tint = color
+            .attr("tint")
+            .and_then(|t| t.parse::<f64>().ok())
+            .unwrap_or(0.0);
+        Some(format!("#{:06X}", apply_tint(rgb, tint)))
+    }
+}
+
+/// Excel と同じく HLS の輝度に tint を適用する
+fn apply_tint(rgb: u32, tint: f64) -> u32 {
+    if tint == 0.0 {
+        return rgb;
+    }
+    let channel = |shift: u32| ((rgb >> shift) & 0xFF) as f64 / 255.0;
+    let (r, g, b) = (channel(16), channel(8), channel(0));
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let lum = (max + min) / 2.0;
+    let (hue, sat) = if max == min {
+        (0.0, 0.0)
+    } else {
+        let d = max - min;
+        let sat = if lum > 0.5 {
+            d / (2.0 - max - min)
+        } else {
+            d / (max + min)
+        };
+        let hue = if max == r {
+            (g - b) / d + if g < b { 6.0 } else { 0.0 }
+        } else if max == g {
+            (b - r) / d + 2.0
+        } else {
+            (r - g) / d + 4.0
+        };
+        (hue / 6.0, sat)
+    };
+
+    let lum = if tint < 0.0 {
+        lum * (1.0 + tint)
+    } else {
+        lum * (1.0 - tint) + tint
+    };
+
+    let to_rgb = |t: f64| -> f64 {
+        let q = if lum < 0.5 {
+            lum * (1.0 + sat)
+        } else {
+            lum + sat - lum * sat
+        };
+        let p = 2.0 * lum - q;
+        let t = t.rem_euclid(1.0);
+        if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        }
+    };
+    let (r, g, b) = if sat == 0.0 {
+        (lum, lum, lum)
+    } else {
+        (
+            to_rgb(hue + 1.0 / 3.0),
+            to_rgb(hue),
+            to_rgb(hue - 1.0 / 3.0),
+        )
+    };
+    let byte = |v: f64| (v * 255.0).round().clamp(0.0, 255.0) as u32;
+    (byte(r) << 16) | (byte(g) << 8) | byte(b)
+}
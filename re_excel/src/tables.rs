@@ -0,0 +1,47 @@
+use crate::model::Table;
+use crate::ooxml::Package;
+
+/// ワークシートに関連付けられたテーブル部品を読む。
+/// calamine の `Table` は範囲や集計行の有無を公開しないため部品を直接読む
+pub fn read_tables(package: &mut Package, sheet: &str, sheet_path: &str) -> Vec<Table> {
+    let targets: Vec<String> = package
+        .relationships(sheet_path)
+        .into_iter()
+        .filter(|r| r.is("table"))
+        .map(|r| r.target)
+        .collect();
+
+    targets
+        .iter()
+        .filter_map(|target| {
+            let table = package.part(target)?;
+            let count = |name: &str, default: u32| {
+                table
+                    .attr(name)
+                    .and_then(|c| c.parse::<u32>().ok())
+                    .unwrap_or(default)
+            };
+            Some(Table {
+                name: table
+                    .attr("displayName")
+                    .or(table.attr("name"))?
+                    .to_string(),
+                sheet: sheet.to_string(),
+                range: table.attr("ref")?.to_string(),
+                header_row: count("headerRowCount", 1) > 0,
+                totals_row: count("totalsRowCount", 0) > 0,
+                columns: table
+                    .child("tableColumns")
+                    .into_iter()
+                    .flat_map(|c| c.children_named("tableColumn"))
+                    .filter_map(|c| c.attr("name"))
+                    .map(ToString::to_string)
+                    .collect(),
+                style: table
+                    .child("tableStyleInfo")
+                    .and_then(|s| s.attr("name"))
+                    .map(ToString::to_string),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,170 @@
+use crate::model::{CellData, CellType, PhoneticRun, TextRun, error_literal, is_true};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// セル値の出力モード。既定は従来どおりの文字列
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ValueMode {
+    #[default]
+    String,
+    /// `value` を種類付きの値（`TypedValue`）で出力する
+    Typed,
+}
+
+impl ValueMode {
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "string" => Some(ValueMode::String),
+            "typed" => Some(ValueMode::Typed),
+            _ => None,
+        }
+    }
+}
+
+/// `Workbook::cells` を出力モードに合わせて直列化する。typed モードでは各セルの `value` を型付きにする
+pub struct TypedCells<'a> {
+    pub cells: Vec<&'a CellData>,
+    pub mode: ValueMode,
+}
+
+impl Serialize for TypedCells<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.mode {
+            ValueMode::String => serializer.collect_seq(&self.cells),
+            ValueMode::Typed => {
+                serializer.collect_seq(self.cells.iter().map(|cell| typed_cell(cell)))
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TypedCell<'a> {
+    sheet: &'a str,
+    address: &'a str,
+    row: u32,
+    col: u32,
+    data_type: CellType,
+    value: TypedValue<'a>,
+    formula: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    serial: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number_format: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_text: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    style_id: Option<usize>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    runs: &'a [TextRun],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    phonetic: &'a [PhoneticRun],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pivot_table: Option<&'a str>,
+    #[serde(skip_serializing_if = "is_true")]
+    locked: bool,
+}
+
+/// 種類を付けた値。`{"type": "number", "value": 1.5}` の形で出力する。
+/// XML でも `<type>number</type>` とテキストで出力するため手動で直列化する
+pub enum TypedValue<'a> {
+    Number(Number),
+    Bool(bool),
+    String(&'a str),
+    /// `#DIV/0!` などのエラー値
+    Error(&'a str),
+    /// 日付・時刻・日時（ISO 8601）
+    Date(&'a str),
+    /// 期間（ISO 8601）
+    Duration(&'a str),
+    Empty,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl TypedValue<'_> {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TypedValue::Number(_) => "number",
+            TypedValue::Bool(_) => "bool",
+            TypedValue::String(_) => "string",
+            TypedValue::Error(_) => "error",
+            TypedValue::Date(_) => "date",
+            TypedValue::Duration(_) => "duration",
+            TypedValue::Empty => "empty",
+        }
+    }
+}
+
+impl Serialize for TypedValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = if matches!(self, TypedValue::Empty) {
+            1
+        } else {
+            2
+        };
+        let mut value = serializer.serialize_struct("value", len)?;
+        value.serialize_field("type", self.type_name())?;
+        match self {
+            TypedValue::Number(n) => value.serialize_field("value", n)?,
+            TypedValue::Bool(b) => value.serialize_field("value", b)?,
+            TypedValue::String(s)
+            | TypedValue::Error(s)
+            | TypedValue::Date(s)
+            | TypedValue::Duration(s) => value.serialize_field("value", s)?,
+            TypedValue::Empty => value.skip_field("value")?,
+        }
+        value.end()
+    }
+}
+
+fn typed_cell(cell: &CellData) -> TypedCell<'_> {
+    TypedCell {
+        sheet: &cell.sheet,
+        address: &cell.address,
+        row: cell.row,
+        col: cell.col,
+        data_type: cell.data_type,
+        value: typed_value(cell),
+        formula: cell.formula.as_deref(),
+        serial: cell.serial,
+        number_format: cell.number_format.as_deref(),
+        display_text: cell.display_text.as_deref(),
+        style_id: cell.style_id,
+        runs: &cell.runs,
+        phonetic: &cell.phonetic,
+        pivot_table: cell.pivot_table.as_deref(),
+        locked: cell.locked,
+    }
+}
+
+/// 文字列化済みの値を data_type に従って型付きに戻す（解釈できなければ文字列のまま）
+pub fn typed_value(cell: &CellData) -> TypedValue<'_> {
+    let value = cell.value.as_str();
+    match cell.data_type {
+        CellType::Empty => TypedValue::Empty,
+        CellType::Number => {
+            if let Ok(i) = value.parse::<i64>() {
+                TypedValue::Number(Number::Int(i))
+            } else {
+                match value.parse::<f64>() {
+                    Ok(f) if f.is_finite() => TypedValue::Number(Number::Float(f)),
+                    _ => TypedValue::String(value),
+                }
+            }
+        }
+        CellType::Boolean => match value.parse::<bool>() {
+            Ok(b) => TypedValue::Bool(b),
+            Err(_) => TypedValue::String(value),
+        },
+        CellType::Error => TypedValue::Error(error_literal(value).unwrap_or(value)),
+        CellType::String => TypedValue::String(value),
+        CellType::DateTime | CellType::DateTimeIso => TypedValue::Date(value),
+        CellType::Duration | CellType::DurationIso => TypedValue::Duration(value),
+    }
+}
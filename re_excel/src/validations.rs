@@ -0,0 +1,87 @@
+use crate::model::DataValidation;
+use crate::ooxml::Element;
+
+/// ワークシートの入力規則を読む。別シートを参照するリストなど、
+/// 拡張領域（`extLst` の x14 形式）に書かれた規則も含める
+pub fn read_validations(sheet: &str, sheet_xml: &Element) -> Vec<DataValidation> {
+    let standard = sheet_xml
+        .child("dataValidations")
+        .into_iter()
+        .flat_map(|d| d.children_named("dataValidation"));
+    let extended = sheet_xml
+        .child("extLst")
+        .into_iter()
+        .flat_map(|e| e.children_named("ext"))
+        .filter_map(|e| e.child("dataValidations"))
+        .flat_map(|d| d.children_named("dataValidation"));
+
+    standard
+        .chain(extended)
+        .filter_map(|dv| read_validation(sheet, dv))
+        .collect()
+}
+
+fn read_validation(sheet: &str, dv: &Element) -> Option<DataValidation> {
+    let attr = |name: &str| dv.attr(name).map(ToString::to_string);
+    let flag = |name: &str| matches!(dv.attr(name), Some("1" | "true"));
+    // x14 形式では範囲と式が子要素（`<xm:sqref>`, `<x14:formula1><xm:f>`）になる
+    let range = dv
+        .attr("sqref")
+        .map(ToString::to_string)
+        .or_else(|| dv.child("sqref").map(|s| s.text.clone()))?;
+    let formula = |name: &str| {
+        dv.child(name)
+            .map(|f| f.child("f").map_or(&f.text, |inner| &inner.text).clone())
+            .filter(|f| !f.is_empty())
+    };
+
+    Some(DataValidation {
+        sheet: sheet.to_string(),
+        range,
+        validation_type: attr("type").unwrap_or_else(|| "none".into()),
+        operator: attr("operator"),
+        formula1: formula("formula1"),
+        formula2: formula("formula2"),
+        allow_blank: flag("allowBlank"),
+        show_dropdown: !flag("showDropDown"),
+        show_input_message: flag("showInputMessage"),
+        show_error_message: flag("showErrorMessage"),
+        error_style: attr("errorStyle"),
+        error_title: attr("errorTitle"),
+        error_message: attr("error"),
+        prompt_title: attr("promptTitle"),
+        prompt_message: attr("prompt"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_standard_and_x14_validations() {
+        let xml = r#"<worksheet><dataValidations count="1"><dataValidation type="whole" operator="between" allowBlank="1" showErrorMessage="1" errorStyle="warning" sqref="A1:A3 C1:C3"><formula1>1</formula1><formula2>10</formula2></dataValidation></dataValidations><extLst><ext uri="{CCE6A557-97BC-4b89-ADB6-D9C93CAAB3DF}" xmlns:x14="http://schemas.microsoft.com/office/spreadsheetml/2009/9/main"><x14:dataValidations count="1" xmlns:xm="http://schemas.microsoft.com/office/excel/2006/main"><x14:dataValidation type="list" allowBlank="1" showDropDown="1"><x14:formula1><xm:f>Lists!$A$1:$A$3</xm:f></x14:formula1><xm:sqref>E1:E5</xm:sqref></x14:dataValidation></x14:dataValidations></ext></extLst></worksheet>"#;
+        let validations =
+            read_validations("Sheet1", &crate::ooxml::parse_xml(xml.as_bytes()).unwrap());
+        assert_eq!(validations.len(), 2);
+
+        let whole = &validations[0];
+        assert_eq!(whole.range, "A1:A3 C1:C3");
+        assert_eq!(whole.validation_type, "whole");
+        assert_eq!(whole.operator.as_deref(), Some("between"));
+        assert_eq!(whole.formula1.as_deref(), Some("1"));
+        assert_eq!(whole.formula2.as_deref(), Some("10"));
+        assert!(whole.allow_blank && whole.show_error_message && whole.show_dropdown);
+        assert_eq!(whole.error_style.as_deref(), Some("warning"));
+
+        // 別シートを参照するリストは x14 形式で、範囲と式が子要素になる
+        let list = &validations[1];
+        assert_eq!(list.sheet, "Sheet1");
+        assert_eq!(list.range, "E1:E5");
+        assert_eq!(list.validation_type, "list");
+        assert_eq!(list.formula1.as_deref(), Some("Lists!$A$1:$A$3"));
+        assert_eq!(list.formula2, None);
+        // `showDropDown="1"` はドロップダウンを隠す指定
+        assert!(!list.show_dropdown);
+    }
+}